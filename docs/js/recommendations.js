@@ -58,6 +58,21 @@ function computeIDF(ingredientMaps) {
   return idf;
 }
 
+// Similar recipes precomputed by the parser ({ [recipeId]: [{ id, score, shared }] }),
+// or null when similar-recipes.json can't be loaded.
+let precomputedSimilar;
+
+async function loadPrecomputedSimilar() {
+  if (precomputedSimilar !== undefined) return precomputedSimilar;
+  try {
+    const response = await fetch('similar-recipes.json');
+    precomputedSimilar = response.ok ? await response.json() : null;
+  } catch {
+    precomputedSimilar = null;
+  }
+  return precomputedSimilar;
+}
+
 // Returns [{ recipe, score, sharedIngredients: string[] }] sorted by score desc.
 // n = max results to return. Excludes the recipe itself.
 // Excludes recipes with score 0 (no shared meaningful ingredients).
@@ -69,6 +84,18 @@ async function getSimilarRecipes(recipeId, n = 5) {
   const showUntestedRecipes = !!(await getSetting('showUntestedRecipes'));
   const dietaryFilters = (await getSetting('dietaryFilters')) || [];
 
+  // Prefer the build-time results, which only need filtering by the user's settings.
+  // Score here instead when they don't cover the loaded recipes (e.g. a stale cache).
+  const precomputed = (await loadPrecomputedSimilar())?.[recipeId];
+  const recipesById = new Map(recipes.map(r => [r.id, r]));
+  if (precomputed && recipesById.has(recipeId) && precomputed.every(s => recipesById.has(s.id))) {
+    return precomputed
+      .map(({ id, score, shared }) => ({ recipe: recipesById.get(id), score, sharedIngredients: shared }))
+      .filter(({ recipe }) => (showUntestedRecipes || recipe.tested !== false)
+        && dietaryFilters.every(d => (recipe.diet || []).includes(d)))
+      .slice(0, n);
+  }
+
   const ingredientMaps = buildRecipeIngredientMaps(recipes, { showUntestedRecipes, dietaryFilters });
   const idf = computeIDF(ingredientMaps);
 
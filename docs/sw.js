@@ -91,6 +91,9 @@ self.addEventListener('fetch', (event) => {
   // Skip non-http requests
   if (!url.protocol.startsWith('http')) return;
 
+  // Don't intercept the recipe preview server's live reload stream
+  if (url.pathname.startsWith('/__')) return;
+
   // Don't intercept recipe data — let recipes.js handle via localStorage
   if (url.pathname.endsWith('recipes.json') || url.pathname.endsWith('recipes-manifest.json')) {
     return;
@@ -0,0 +1,197 @@
+//! Incremental build cache. Recipes that parsed without any diagnostics are stored with the
+//! SHA-256 of their source, and reused on the next run when the source, the vocabulary and
+//! the parser binary are all unchanged.
+
+use crate::Recipe;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default location, relative to the repo root. Lives under `target/` so CI's cargo cache
+/// carries it between runs and `cargo clean` clears it.
+pub(crate) const DEFAULT_CACHE_PATH: &str = "recipe-parser/target/.recipe-cache";
+
+#[derive(Deserialize, Serialize)]
+struct CacheFile {
+    /// Parser version and vocabulary hash; any change invalidates every entry.
+    key: String,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct CacheEntry {
+    /// SHA-256 of the recipe source.
+    hash: String,
+    /// Whether the recipe was also clean under `--lint`, so it can be reused in either mode.
+    lint: bool,
+    recipe: Recipe,
+}
+
+pub(crate) struct BuildCache {
+    path: PathBuf,
+    key: String,
+    lint: bool,
+    previous: BTreeMap<String, CacheEntry>,
+    /// Entries for this run; files that were deleted or stopped parsing cleanly drop out.
+    current: BTreeMap<String, CacheEntry>,
+    pub(crate) hits: usize,
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Identifies the parser build. The package version alone isn't bumped when the parsing code
+/// changes, so the running executable's size and modification time are included too.
+fn parser_version() -> String {
+    let exe = std::env::current_exe().and_then(fs::metadata).ok();
+    let build = exe.map(|m| {
+        let modified = m.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        format!("{}-{}", m.len(), modified)
+    });
+    format!("{}+{}", env!("CARGO_PKG_VERSION"), build.unwrap_or_default())
+}
+
+impl BuildCache {
+    /// Load the cache at `path`. A missing, unreadable or outdated cache starts empty.
+    pub(crate) fn load(path: &Path, vocabulary: &Path, lint: bool) -> Self {
+        let vocabulary_hash = fs::read(vocabulary).map(|bytes| sha256_hex(&bytes)).unwrap_or_default();
+        let key = format!("{}:{}", parser_version(), vocabulary_hash);
+        let previous = fs::read_to_string(path).ok()
+            .and_then(|json| serde_json::from_str::<CacheFile>(&json).ok())
+            .filter(|cache| cache.key == key)
+            .map(|cache| cache.entries)
+            .unwrap_or_default();
+        Self { path: path.to_path_buf(), key, lint, previous, current: BTreeMap::new(), hits: 0 }
+    }
+
+    /// The cached recipe for `file`, if its source is unchanged. Safe to call from many
+    /// threads; record the outcome afterwards with `keep` or `insert`.
+    pub(crate) fn get(&self, file: &Path, source: &str) -> Option<Recipe> {
+        let entry = self.previous.get(&file.display().to_string())?;
+        if entry.hash != sha256_hex(source.as_bytes()) || (self.lint && !entry.lint) {
+            return None;
+        }
+        Some(entry.recipe.clone())
+    }
+
+    /// Carry the entry returned by `get` over into this run's cache.
+    pub(crate) fn keep(&mut self, file: &Path) {
+        let name = file.display().to_string();
+        if let Some(entry) = self.previous.get(&name) {
+            self.hits += 1;
+            self.current.insert(name, entry.clone());
+        }
+    }
+
+    /// Remember a recipe that parsed without any diagnostics.
+    pub(crate) fn insert(&mut self, file: &Path, source: &str, recipe: &Recipe) {
+        let entry = CacheEntry { hash: sha256_hex(source.as_bytes()), lint: self.lint, recipe: recipe.clone() };
+        self.current.insert(file.display().to_string(), entry);
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create cache directory: {:?}", parent))?;
+        }
+        let cache = CacheFile { key: self.key.clone(), entries: self.current.clone() };
+        let json = serde_json::to_string(&cache).context("Failed to serialize build cache")?;
+        fs::write(&self.path, json)
+            .with_context(|| format!("Failed to write build cache: {:?}", self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Diagnostics;
+    use crate::fixtures::TestRecipe;
+    use crate::{parse_recipe, CanonicalData};
+
+    fn source() -> String {
+        TestRecipe::new("cached-recipe")
+            .ingredients("- 1 1/2 tbsp [olive oil]\n- 400 g (1-3/4 cups) [flour]")
+            .steps("1. Mix the {olive oil} and {flour} and rest for 10 minutes.")
+            .source()
+    }
+
+    fn parsed() -> Recipe {
+        parse_recipe(&source(), true, &CanonicalData::empty(), &mut Diagnostics::default()).unwrap()
+    }
+
+    fn temp_paths(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("recipe-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("ingredients.json"), "{}").unwrap();
+        (dir.join(".recipe-cache"), dir.join("ingredients.json"))
+    }
+
+    #[test]
+    fn test_cached_recipe_serializes_identically() {
+        let recipe = parsed();
+        let json = serde_json::to_string_pretty(&recipe).unwrap();
+        let roundtrip: Recipe = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&roundtrip).unwrap(), json);
+    }
+
+    #[test]
+    fn test_reuses_unchanged_recipes() {
+        let (path, vocabulary) = temp_paths("reuse");
+        let recipe = source();
+        let file = Path::new("recipes/cached-recipe.md");
+
+        let mut cache = BuildCache::load(&path, &vocabulary, true);
+        assert!(cache.get(file, &recipe).is_none());
+        cache.insert(file, &recipe, &parsed());
+        cache.save().unwrap();
+
+        let mut cache = BuildCache::load(&path, &vocabulary, true);
+        assert_eq!(cache.get(file, &recipe).unwrap().id, "cached-recipe");
+        assert!(cache.get(file, &recipe.replace("Recipe cached-recipe", "Changed")).is_none());
+        cache.keep(file);
+        assert_eq!(cache.hits, 1);
+        cache.save().unwrap();
+
+        // Entries from a lint run serve plain builds too, but not the other way round
+        let mut plain = BuildCache::load(&path, &vocabulary, false);
+        assert!(plain.get(file, &recipe).is_some());
+        plain.insert(file, &recipe, &parsed());
+        plain.save().unwrap();
+        assert!(BuildCache::load(&path, &vocabulary, true).get(file, &recipe).is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_vocabulary_change_invalidates_cache() {
+        let (path, vocabulary) = temp_paths("vocabulary");
+        let recipe = source();
+        let file = Path::new("recipes/cached-recipe.md");
+        let mut cache = BuildCache::load(&path, &vocabulary, true);
+        cache.insert(file, &recipe, &parsed());
+        cache.save().unwrap();
+
+        fs::write(&vocabulary, r#"{"ingredients": {}}"#).unwrap();
+        assert!(BuildCache::load(&path, &vocabulary, true).get(file, &recipe).is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_cache_starts_empty() {
+        let (path, vocabulary) = temp_paths("corrupt");
+        fs::write(&path, "not json").unwrap();
+        let cache = BuildCache::load(&path, &vocabulary, true);
+        assert!(cache.get(Path::new("recipes/cached-recipe.md"), &source()).is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
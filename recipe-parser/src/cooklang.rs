@@ -0,0 +1,713 @@
+//! `convert --to cooklang` / `--from cooklang`: move recipes between our format and
+//! [Cooklang](https://cooklang.org).
+//!
+//! Export writes the frontmatter as Cooklang metadata and each step as a paragraph. Every
+//! `{ref}` becomes an `@ingredient{}`; the first reference to an ingredient carries its
+//! quantity and its preparation as a note, e.g. `@garlic{3%cloves}(minced)`. Step durations
+//! become `~{20%minutes}` timers. Import reverses this: ingredients are listed in the order
+//! they are first used, with quantities of repeated ingredients added up.
+//!
+//! What doesn't survive a round trip: ingredient groups, brackets in preparation notes
+//! (Cooklang notes can't contain them), durations written in words ("fifteen minutes" comes
+//! back as "15 minutes"), and step refs with no ingredient line, which Cooklang lists as
+//! ingredients.
+
+use crate::diagnostics::{self, Diagnostics, FileReport, Format};
+use crate::import::{self, Imported};
+use crate::new::{self, Frontmatter};
+use crate::{
+    duration_re, parse_duration_num, parse_recipe, parse_step_durations, step_ref_matches, CanonicalData,
+    Ingredient, Recipe, RecipeFormat, VALID_CUISINES, VALID_DIETS, VALID_DIFFICULTIES, VALID_MEAL_TYPES,
+};
+use anyhow::{bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const SERVING_SUGGESTIONS: &str = "Serving Suggestions";
+
+/// Ingredient prefixes the quantity parser understands, kept as the start of the note.
+const PREFIXES: &[&str] = &["Juice of", "Zest of"];
+
+/// Convert `file` to or from Cooklang. Exports go to `output` or stdout; imports go to
+/// `output` or a new file in the recipes directory, listing what needs reviewing.
+pub(crate) fn run(to: Option<RecipeFormat>, file: &Path, output: Option<&Path>, input: &Path, canonical: &CanonicalData) -> Result<()> {
+    let source = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read file: {:?}", file))?;
+
+    if to.is_none() {
+        let stem = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let imported = import(&source, &stem, canonical, new::today())
+            .with_context(|| format!("Failed to read Cooklang recipe: {}", file.display()))?;
+        import::require_id(&imported, "add an `id` to its metadata")?;
+        let path = match output {
+            Some(path) => path.to_path_buf(),
+            None => new::target_path(input, &imported.frontmatter.id)?,
+        };
+        return import::save(&imported, &path, canonical);
+    }
+
+    let mut diagnostics = Diagnostics::default();
+    let Some(recipe) = parse_recipe(&source, false, canonical, &mut diagnostics) else {
+        diagnostics::print_reports(&[FileReport { file: file.display().to_string(), source, diagnostics }], Format::Text);
+        bail!("Failed to parse {}", file.display());
+    };
+    let (cook, warnings) = export(&recipe, canonical);
+    for warning in &warnings {
+        eprintln!("⚠️  {}", warning);
+    }
+    match output {
+        Some(path) => {
+            fs::write(path, cook).with_context(|| format!("Failed to write file: {:?}", path))?;
+            println!("🍳 Converted '{}' to {}", recipe.name, path.display());
+        }
+        None => print!("{}", cook),
+    }
+    Ok(())
+}
+
+/// Convert a parsed recipe to Cooklang. Also returns warnings about anything that had to
+/// be moved around to fit.
+pub(crate) fn export(recipe: &Recipe, canonical: &CanonicalData) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut out = String::from("---\n");
+    out.push_str(&format!("title: {}\n", yaml_scalar(&recipe.name)));
+    out.push_str(&format!("id: {}\n", recipe.id));
+    out.push_str(&format!("description: {}\n", yaml_scalar(&recipe.description)));
+    out.push_str(&format!("servings: {}\n", recipe.servings));
+    out.push_str(&format!("time: {} minutes\n", recipe.time));
+    out.push_str(&format!("difficulty: {}\n", recipe.difficulty));
+    out.push_str(&format!("diet: [{}]\n", recipe.diet.join(", ")));
+    out.push_str(&format!("cuisine: [{}]\n", recipe.cuisine.join(", ")));
+    out.push_str(&format!("course: [{}]\n", recipe.meal_type.join(", ")));
+    out.push_str(&format!("date: {}\n", recipe.date));
+    out.push_str(&format!("tested: {}\n", recipe.tested));
+    out.push_str("---\n");
+
+    if let Some(notes) = &recipe.notes {
+        out.push('\n');
+        out.push_str(&quote_note(notes));
+    }
+
+    let mut ingredients: Vec<&Ingredient> = recipe.ingredients.values().flatten().collect();
+    ingredients.sort_by_key(|i| i.id);
+    let mut used = HashSet::new();
+    let mut steps: Vec<String> = recipe.steps.iter()
+        .map(|step| add_timers(&export_refs(&step.text, &ingredients, &mut used, canonical)))
+        .collect();
+    // "--" starts a comment in Cooklang
+    if steps.iter().any(|step| step.contains("--")) {
+        warnings.push("'--' in a step would start a Cooklang comment; replaced with '—'".to_string());
+        steps.iter_mut().for_each(|step| *step = step.replace("--", "—"));
+    }
+
+    // Cooklang has no ingredient list of its own, so unused ingredients go in a step
+    let unused: Vec<String> = ingredients.iter()
+        .filter(|i| !used.contains(&i.id))
+        .map(|i| cook_ingredient(i, i.canonical.as_deref().unwrap_or(&i.text)))
+        .collect();
+    if !unused.is_empty() {
+        warnings.push(format!("{} ingredient(s) aren't used in any step; they were added to a first step", unused.len()));
+        steps.insert(0, format!("You will also need {}.", unused.join(", ")));
+    }
+    for step in steps {
+        out.push('\n');
+        out.push_str(&step);
+        out.push('\n');
+    }
+
+    if let Some(suggestions) = &recipe.serving_suggestions {
+        out.push_str(&format!("\n= {}\n\n", SERVING_SUGGESTIONS));
+        out.push_str(&quote_note(suggestions));
+    }
+    (out, warnings)
+}
+
+fn quote_note(text: &str) -> String {
+    text.lines().map(|l| if l.is_empty() { ">\n".to_string() } else { format!("> {}\n", l) }).collect()
+}
+
+fn yaml_scalar(s: &str) -> String {
+    match serde_yaml::from_str::<Value>(s) {
+        Ok(Value::String(parsed)) if parsed == s => s.to_string(),
+        _ => serde_json::to_string(s).expect("string serializes"),
+    }
+}
+
+/// Replace each `{ref}` with an `@ingredient{}`, with the quantity on its first use.
+fn export_refs(text: &str, ingredients: &[&Ingredient], used: &mut HashSet<u32>, canonical: &CanonicalData) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|c| open + c) else { break };
+        out.push_str(&rest[..open]);
+        let name = &rest[open + 1..close];
+        let matched = ingredients.iter().find(|i| step_ref_matches(i, &name.to_lowercase(), canonical));
+        match matched {
+            Some(ingredient) if used.insert(ingredient.id) => out.push_str(&cook_ingredient(ingredient, name)),
+            _ => out.push_str(&format!("@{}{{}}", name)),
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `@name{quantity%unit}(note)` for an ingredient line. The quantity is written as in the
+/// line, so mixed numbers like "1 1/2" and ranges survive as they are; words before the name
+/// ("A handful of") become a text quantity.
+fn cook_ingredient(ingredient: &Ingredient, name: &str) -> String {
+    let text = ingredient.text.trim();
+    let span = find_name(text, name).or_else(|| ingredient.canonical.as_deref().and_then(|c| find_name(text, c)));
+    let (mut before, after) = match &span {
+        Some(span) => (text[..span.start].trim(), &text[span.end..]),
+        None => ("", ingredient.preparation.as_deref().unwrap_or_default()),
+    };
+
+    let mut notes = Vec::new();
+    if let Some(prefix) = PREFIXES.iter().find(|p| before.get(..p.len()).is_some_and(|b| b.eq_ignore_ascii_case(p))) {
+        notes.push(before[..prefix.len()].to_string());
+        before = before[prefix.len()..].trim_start();
+    }
+    let quantity = match &ingredient.quantity {
+        Some(q) if span.is_none() => match &q.unit {
+            Some(unit) => format!("{}%{}", format_amount(q.amount), unit),
+            None => format_amount(q.amount),
+        },
+        Some(_) => {
+            let end = before.find(|c: char| !(c.is_ascii_digit() || " ./-–".contains(c))).unwrap_or(before.len());
+            let amount = before[..end].trim().replace('–', "-");
+            let mut unit = before[end..].trim();
+            // "1 tin (400 g)": the secondary quantity goes in the note
+            if let Some((main, secondary)) = unit.split_once('(') {
+                notes.push(secondary.trim_end_matches(')').trim().to_string());
+                unit = main.trim();
+            }
+            if unit.is_empty() { amount } else { format!("{}%{}", amount, unit) }
+        }
+        None => before.to_string(),
+    };
+    notes.push(after.trim_start_matches([',', ' ']).trim().to_string());
+    notes.retain(|n| !n.is_empty());
+    // Cooklang notes end at the first ')', so brackets inside them are dropped
+    let note = if notes.is_empty() { String::new() } else { format!("({})", notes.join(", ").replace(['(', ')'], "")) };
+    format!("@{}{{{}}}{}", name, quantity, note)
+}
+
+/// Where `name` starts as a word in `text`, up to the end of that word ("egg" in "eggs").
+fn find_name(text: &str, name: &str) -> Option<std::ops::Range<usize>> {
+    let lower = text.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+    lower.match_indices(&name)
+        .map(|(start, _)| start)
+        .find(|&start| !lower[..start].ends_with(char::is_alphanumeric))
+        .map(|start| {
+            let rest = &lower[start + name.len()..];
+            let end = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+            start..start + name.len() + end
+        })
+}
+
+/// Kitchen-friendly amount: whole numbers and common fractions as fractions, anything else
+/// as a short decimal.
+fn format_amount(amount: f64) -> String {
+    let whole = amount.trunc();
+    let fraction = amount - whole;
+    if fraction.abs() < 0.01 {
+        return format!("{}", whole as i64);
+    }
+    for (value, text) in [(0.25, "1/4"), (1.0 / 3.0, "1/3"), (0.5, "1/2"), (2.0 / 3.0, "2/3"), (0.75, "3/4")] {
+        if (fraction - value).abs() < 0.01 {
+            return if whole == 0.0 { text.to_string() } else { format!("{} {}", whole as i64, text) };
+        }
+    }
+    format!("{:.2}", amount).trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Turn duration mentions into `~{quantity%unit}` timers, leaving "for"/"about" as text.
+fn add_timers(text: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for cap in duration_re().captures_iter(text) {
+        let (number, unit) = (cap.get(1).unwrap(), cap.get(3).unwrap());
+        if inside_braces(text, number.start()) {
+            continue;
+        }
+        let value = |m: regex::Match| match m.as_str().parse::<f64>() {
+            Ok(_) => m.as_str().to_string(),
+            Err(_) => parse_duration_num(m.as_str()).to_string(),
+        };
+        let quantity = match cap.get(2) {
+            Some(max) => format!("{}-{}", value(number), value(max)),
+            None => value(number),
+        };
+        out.push_str(&text[last..number.start()]);
+        out.push_str(&format!("~{{{}%{}}}", quantity, unit.as_str()));
+        last = unit.end();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+fn inside_braces(text: &str, at: usize) -> bool {
+    let before = &text[..at];
+    before.rfind('{').is_some_and(|open| before.rfind('}').is_none_or(|close| close < open))
+}
+
+/// An ingredient collected from the steps of a Cooklang recipe.
+struct CookIngredient {
+    /// Vocabulary form (or lowercase name) used to merge mentions of the same ingredient.
+    key: String,
+    name: String,
+    quantity: Option<String>,
+    unit: Option<String>,
+    note: Option<String>,
+}
+
+/// Convert a Cooklang recipe into our format. `fallback_id` names the recipe when the
+/// metadata has no title.
+pub(crate) fn import(source: &str, fallback_id: &str, canonical: &CanonicalData, date: String) -> Result<Imported> {
+    let source = source.replace("\r\n", "\n");
+    let (mut metadata, body) = split_metadata(&source)?;
+    let body = strip_comments(body);
+
+    let mut review = Vec::new();
+    let mut notes: Vec<String> = Vec::new();
+    let mut suggestions: Vec<String> = Vec::new();
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut section = String::new();
+    for line in body.lines().map(str::trim) {
+        if let Some(entry) = line.strip_prefix(">>") {
+            if let Some((key, value)) = entry.split_once(':') {
+                metadata.insert(Value::String(key.trim().to_string()), Value::String(value.trim().to_string()));
+            }
+        } else if let Some(note) = line.strip_prefix('>') {
+            let target = if section == SERVING_SUGGESTIONS { &mut suggestions } else { &mut notes };
+            target.push(note.trim().to_string());
+        } else if line.starts_with('=') {
+            section = line.trim_matches(|c: char| c == '=' || c.is_whitespace()).to_string();
+        } else if !line.is_empty() {
+            paragraph.push(line);
+            continue;
+        }
+        if !paragraph.is_empty() {
+            paragraphs.push(paragraph.join(" "));
+            paragraph.clear();
+        }
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph.join(" "));
+    }
+
+    let mut ingredients: Vec<CookIngredient> = Vec::new();
+    let steps: Vec<String> = paragraphs.iter()
+        .map(|p| import_step(p, &mut ingredients, canonical, &mut review))
+        .collect();
+    let ingredient_lines: Vec<String> = ingredients.iter().map(ingredient_line).collect();
+    if steps.is_empty() {
+        review.push("instructions: the recipe has no steps".to_string());
+    }
+
+    let frontmatter = import_frontmatter(&metadata, fallback_id, date, &mut review);
+    let paragraph_text = |lines: Vec<String>| {
+        let text = lines.join("\n").trim().to_string();
+        (!text.is_empty()).then_some(text)
+    };
+    Ok(Imported {
+        frontmatter,
+        notes: paragraph_text(notes),
+        ingredients: ingredient_lines,
+        steps,
+        serving_suggestions: paragraph_text(suggestions),
+        review,
+    })
+}
+
+/// YAML front matter (if any) and the rest of the file.
+fn split_metadata(source: &str) -> Result<(Mapping, &str)> {
+    let Some(rest) = source.strip_prefix("---\n") else {
+        return Ok((Mapping::new(), source));
+    };
+    let Some(end) = rest.find("\n---") else {
+        bail!("Unclosed metadata block: missing the closing '---'");
+    };
+    let metadata = match serde_yaml::from_str::<Value>(&rest[..end])? {
+        Value::Mapping(mapping) => mapping,
+        _ => Mapping::new(),
+    };
+    let body = rest[end + 4..].trim_start_matches('-');
+    Ok((metadata, body))
+}
+
+/// Remove `-- line comments` and `[- block comments -]`.
+fn strip_comments(body: &str) -> String {
+    let mut out = String::new();
+    let mut rest = body;
+    while let Some(open) = rest.find("[-") {
+        out.push_str(&rest[..open]);
+        rest = rest[open..].find("-]").map(|close| &rest[open + close + 2..]).unwrap_or_default();
+    }
+    out.push_str(rest);
+    out.lines()
+        .map(|line| match line.find("--") {
+            Some(i) if i == 0 || line[..i].ends_with(' ') => line[..i].trim_end(),
+            _ => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Convert one step, collecting its ingredients: `@name{qty%unit}(note)` → `{name}`,
+/// `#pan{}` → `pan`, `~{10%minutes}` → `10 minutes`.
+fn import_step(text: &str, ingredients: &mut Vec<CookIngredient>, canonical: &CanonicalData, review: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find(['@', '#', '~']) {
+        out.push_str(&rest[..i]);
+        let marker = rest.as_bytes()[i];
+        let Some((name, amount, after)) = component(&rest[i + 1..]) else {
+            out.push(marker as char);
+            rest = &rest[i + 1..];
+            continue;
+        };
+        rest = after;
+        let (quantity, unit) = split_quantity(amount.unwrap_or_default());
+        match marker {
+            b'@' => {
+                let mut note = None;
+                if let Some(inner) = rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+                    note = Some(inner.0.trim().to_string()).filter(|n| !n.is_empty());
+                    rest = inner.1;
+                }
+                add_ingredient(ingredients, name, quantity, unit, note, canonical, review);
+                out.push_str(&format!("{{{}}}", name));
+            }
+            b'#' => out.push_str(name),
+            _ => {
+                let duration = [quantity, unit].into_iter().flatten().collect::<Vec<_>>().join(" ");
+                out.push_str(if duration.is_empty() { name } else { &duration });
+            }
+        }
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The name and `{amount}` of a component after its marker, and the text that follows.
+/// Multi-word names need the braces; single words may leave them off.
+fn component(text: &str) -> Option<(&str, Option<&str>, &str)> {
+    if let Some(open) = text.find('{') {
+        let name = &text[..open];
+        let single_name = !name.contains(|c: char| "@#~{}()\n.,;:!?".contains(c));
+        if single_name {
+            let close = open + text[open..].find('}')?;
+            return Some((name.trim(), Some(&text[open + 1..close]), &text[close + 1..]));
+        }
+    }
+    let end = text.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_')).unwrap_or(text.len());
+    (end > 0).then(|| (&text[..end], None, &text[end..]))
+}
+
+/// `3%cloves` → ("3", "cloves"); `2 tbsp` is accepted too.
+fn split_quantity(amount: &str) -> (Option<String>, Option<String>) {
+    let amount = amount.trim();
+    let (quantity, unit) = match amount.split_once('%') {
+        Some((q, u)) => (q.trim(), u.trim()),
+        None => match amount.rsplit_once(' ') {
+            Some((q, u)) if q.starts_with(|c: char| c.is_ascii_digit()) && !u.starts_with(|c: char| c.is_ascii_digit()) => (q.trim(), u.trim()),
+            _ => (amount, ""),
+        },
+    };
+    let some = |s: &str| (!s.is_empty()).then(|| s.to_string());
+    (some(quantity), some(unit))
+}
+
+fn add_ingredient(
+    ingredients: &mut Vec<CookIngredient>,
+    name: &str,
+    quantity: Option<String>,
+    unit: Option<String>,
+    note: Option<String>,
+    canonical: &CanonicalData,
+    review: &mut Vec<String>,
+) {
+    let lower = name.to_lowercase();
+    let key = canonical.lookup_ingredient(&lower).map(str::to_string).unwrap_or(lower);
+    let Some(existing) = ingredients.iter_mut().find(|i| i.key == key) else {
+        ingredients.push(CookIngredient { key, name: name.to_string(), quantity, unit, note });
+        return;
+    };
+    let Some(extra) = quantity else { return };
+    let Some(current) = existing.quantity.clone() else {
+        (existing.quantity, existing.unit) = (Some(extra), unit);
+        return;
+    };
+    // Cooklang adds up repeated ingredients; do the same when the amounts can be added
+    match (amount_value(&current), amount_value(&extra)) {
+        (Some(a), Some(b)) if same_unit(existing.unit.as_deref(), unit.as_deref()) => existing.quantity = Some(format_amount(a + b)),
+        _ => review.push(format!(
+            "ingredient '{}': used with different quantities ({} and {}); kept the first",
+            existing.name,
+            [Some(current), existing.unit.clone()].into_iter().flatten().collect::<Vec<_>>().join(" "),
+            [Some(extra), unit].into_iter().flatten().collect::<Vec<_>>().join(" "),
+        )),
+    }
+}
+
+/// "clove" and "cloves" are the same unit.
+fn same_unit(a: Option<&str>, b: Option<&str>) -> bool {
+    let singular = |u: Option<&str>| u.map(|u| u.trim_end_matches('s').to_lowercase());
+    singular(a) == singular(b)
+}
+
+/// Numeric value of "2", "1.5", "1/2" or "1 1/2".
+fn amount_value(amount: &str) -> Option<f64> {
+    amount.split_whitespace().map(|part| match part.split_once('/') {
+        Some((n, d)) => Some(n.parse::<f64>().ok()? / d.parse::<f64>().ok().filter(|d| *d != 0.0)?),
+        None => part.parse::<f64>().ok(),
+    }).sum()
+}
+
+fn ingredient_line(ingredient: &CookIngredient) -> String {
+    let mut line: Vec<String> = [ingredient.quantity.clone(), ingredient.unit.clone()].into_iter().flatten().collect();
+    let mut note = ingredient.note.as_deref().map(|n| n.split(", ").collect::<Vec<_>>()).unwrap_or_default();
+    // Notes written by `export`: "Juice of" goes first, "400 g" is the secondary quantity of "1 tin (400 g)"
+    if let Some(prefix) = note.first().filter(|n| PREFIXES.iter().any(|p| n.eq_ignore_ascii_case(p))) {
+        line.insert(0, prefix.to_string());
+        note.remove(0);
+    }
+    if let Some(secondary) = note.first().filter(|n| ingredient.quantity.is_some() && is_quantity(n)) {
+        line.push(format!("({})", secondary));
+        note.remove(0);
+    }
+    line.push(format!("[{}]", ingredient.name));
+    let note = (!note.is_empty()).then(|| note.join(", "));
+    let mut line = line.join(" ");
+    if let Some(note) = note {
+        line.push_str(&format!(", {}", note));
+    }
+    line
+}
+
+/// "400 g" or "about 600 g", as left in a note by `export`.
+fn is_quantity(note: &str) -> bool {
+    let note = note.strip_prefix("about ").unwrap_or(note);
+    note.starts_with(|c: char| c.is_ascii_digit()) && note.split_whitespace().count() <= 2
+}
+
+fn import_frontmatter(metadata: &Mapping, fallback_id: &str, date: String, review: &mut Vec<String>) -> Frontmatter {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| metadata.get(*key)).cloned().unwrap_or(Value::Null);
+    let text = |keys: &[&str]| match get(keys) {
+        Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+
+    let name = text(&["title", "name"]).unwrap_or_else(|| {
+        review.push("name: the recipe has no title; named after the file".to_string());
+        fallback_id.split(['-', '_']).filter(|w| !w.is_empty()).map(|w| {
+            let mut chars = w.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        }).collect::<Vec<_>>().join(" ")
+    });
+    let id = text(&["id"]).unwrap_or_else(|| new::slugify(&name));
+
+    let description = text(&["description", "introduction"]).unwrap_or_else(|| {
+        review.push("description: the recipe has none; write one".to_string());
+        name.clone()
+    });
+
+    let servings = match text(&["servings", "serves", "yield"]).as_deref().and_then(import::first_number) {
+        Some(n) if (1..=100).contains(&n) => n,
+        _ => {
+            review.push("servings: not in the metadata; guessed 4".to_string());
+            4
+        }
+    };
+
+    let minutes = |keys: &[&str]| text(keys).and_then(|t| duration_minutes(&t));
+    let time = match minutes(&["time", "total time", "duration"]) {
+        Some(time) => time,
+        None => match (minutes(&["prep time", "time.prep"]), minutes(&["cook time", "time.cook"])) {
+            (None, None) => {
+                review.push("time: not in the metadata; guessed 30 minutes".to_string());
+                30
+            }
+            (prep, cook) => prep.unwrap_or(0) + cook.unwrap_or(0),
+        },
+    };
+
+    let difficulty = match text(&["difficulty"]).map(|d| d.to_lowercase()) {
+        Some(d) if VALID_DIFFICULTIES.contains(&d.as_str()) => d,
+        _ => {
+            let guess = match time {
+                0..=30 => "easy",
+                31..=90 => "medium",
+                _ => "hard",
+            };
+            review.push(format!("difficulty: not in the metadata; guessed '{}' from the time", guess));
+            guess.to_string()
+        }
+    };
+
+    let json = |keys: &[&str]| serde_json::to_value(get(keys)).unwrap_or_default();
+    let diet = import::mapped(&json(&["diet"]), |d| VALID_DIETS.iter().find(|v| **v == d.trim()).copied(), "diet", VALID_DIETS, review);
+    let cuisine = import::mapped(&json(&["cuisine"]), import::cuisine_for, "cuisine", VALID_CUISINES, review);
+    let meal_type = import::mapped(&json(&["course", "meal_type", "meal type"]), import::meal_type_for, "meal_type", VALID_MEAL_TYPES, review);
+
+    let date = text(&["date"]).unwrap_or(date);
+    let tested = match get(&["tested"]) {
+        Value::Bool(tested) => Some(tested),
+        _ => None,
+    };
+    Frontmatter { id, name, description, servings, time, difficulty, diet, cuisine, meal_type, date, tested }
+}
+
+/// Minutes in "35", "35 minutes" or "1 hour 30 minutes".
+fn duration_minutes(text: &str) -> Option<u32> {
+    if let Ok(minutes) = text.trim().parse::<u32>() {
+        return Some(minutes);
+    }
+    let seconds: u32 = parse_step_durations(text).iter().map(|d| d.seconds).sum();
+    (seconds > 0).then(|| seconds.div_ceil(60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Diagnostics;
+    use crate::parse_recipe;
+
+    const RECIPE: &str = "---
+id: cooklang-curry
+name: Cooklang Curry
+description: A recipe used to exercise the Cooklang conversion
+servings: 4
+time: 45
+difficulty: easy
+diet: [vegan, gluten-free]
+cuisine: [indian]
+meal_type: [dinner]
+date: 2025-01-01
+tested: true
+---
+
+# Notes
+
+Keeps well for three days.
+
+# Ingredients
+
+- 1 [onion], finely diced
+- 3 cloves [garlic], minced
+- 1 1/2 tbsp [olive oil]
+
+- 250 g [lentils]
+- 3-4 [tomatoes], chopped
+- [Salt] to taste
+- 1 tin (400 g) [chickpeas], drained
+- Juice of 1/2 [lemon]
+
+# Instructions
+
+1. Heat the {olive oil} and fry the {onion} for 5 minutes.
+2. Add the {garlic} and the {lentils}, then the {tomatoes}, and simmer for 20-25 minutes.
+3. Add the {chickpeas}, season with {salt} and {lemon} and stir in the rest of the {onion}.
+
+# Serving Suggestions
+
+With rice.
+";
+
+    fn vocabulary() -> CanonicalData {
+        let mut data = CanonicalData::empty();
+        data.units = ["cloves", "clove", "tbsp", "tin", "g"].map(String::from).to_vec();
+        data
+    }
+
+    fn recipe(source: &str) -> Recipe {
+        let mut diagnostics = Diagnostics::default();
+        parse_recipe(source, false, &vocabulary(), &mut diagnostics)
+            .unwrap_or_else(|| panic!("{:?}", diagnostics.iter().map(|d| &d.message).collect::<Vec<_>>()))
+    }
+
+    #[test]
+    fn test_exports_cooklang() {
+        let (cook, warnings) = export(&recipe(RECIPE), &vocabulary());
+        assert!(warnings.is_empty());
+        assert!(cook.starts_with("---\ntitle: Cooklang Curry\nid: cooklang-curry\n"));
+        assert!(cook.contains("time: 45 minutes\n"));
+        assert!(cook.contains("course: [dinner]\ndate: 2025-01-01\ntested: true\n---\n\n> Keeps well for three days.\n"));
+        assert!(cook.contains("\nHeat the @olive oil{1 1/2%tbsp} and fry the @onion{1}(finely diced) for ~{5%minutes}.\n"));
+        assert!(cook.contains("\nAdd the @garlic{3%cloves}(minced) and the @lentils{250%g}, then the @tomatoes{3-4}(chopped), and simmer for ~{20-25%minutes}.\n"));
+        assert!(cook.contains("\nAdd the @chickpeas{1%tin}(400 g, drained), season with @salt{}(to taste) and @lemon{1/2}(Juice of) and stir in the rest of the @onion{}.\n"));
+        assert!(cook.ends_with("\n= Serving Suggestions\n\n> With rice.\n"));
+    }
+
+    #[test]
+    fn test_timers_from_words_use_numbers() {
+        assert_eq!(add_timers("Simmer for about fifteen minutes, then rest for 1 hour."), "Simmer for about ~{15%minutes}, then rest for ~{1%hour}.");
+        assert_eq!(add_timers("Cook for three to five minutes."), "Cook for ~{3-5%minutes}.");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let original = recipe(RECIPE);
+        let (cook, _) = export(&original, &vocabulary());
+        let imported = import(&cook, "ignored", &vocabulary(), "2026-10-17".into()).unwrap();
+        assert!(imported.review.is_empty(), "{:?}", imported.review);
+        let round_tripped = recipe(&import::render(&imported));
+
+        let json = |r: &Recipe| {
+            let mut value = serde_json::to_value(r).unwrap();
+            value.as_object_mut().unwrap().remove("ingredients");
+            value
+        };
+        assert_eq!(json(&round_tripped), json(&original));
+
+        let summary = |r: &Recipe| {
+            let mut lines: Vec<String> = r.ingredients.values().flatten()
+                .map(|i| format!("{:?} {:?} {:?}", i.canonical, i.preparation, i.quantity.as_ref().map(|q| (q.amount, q.amount_max, q.unit.clone()))))
+                .collect();
+            lines.sort();
+            lines
+        };
+        assert_eq!(summary(&round_tripped), summary(&original));
+    }
+
+    #[test]
+    fn test_imports_cooklang() {
+        let cook = ">> servings: 2
+>> course: dinner
+
+Crush the @garlic{2%cloves} in a #mortar{} -- keep it fine
+and fry in @olive oil{1%tbsp} for ~{2%minutes}.
+
+[- the pasta should be al dente -]
+Boil @spaghetti{200%g}(dried) for ~pasta{10%minutes}. Toss with the @garlic{1%clove} and @parmesan.
+";
+        let imported = import(cook, "garlic-spaghetti", &vocabulary(), "2026-10-17".into()).unwrap();
+        assert_eq!(imported.frontmatter.name, "Garlic Spaghetti");
+        assert_eq!(imported.frontmatter.servings, 2);
+        assert_eq!(imported.frontmatter.meal_type, vec!["dinner"]);
+        assert_eq!(imported.steps, vec![
+            "Crush the {garlic} in a mortar and fry in {olive oil} for 2 minutes.",
+            "Boil {spaghetti} for 10 minutes. Toss with the {garlic} and {parmesan}.",
+        ]);
+        assert_eq!(imported.ingredients, vec!["3 cloves [garlic]", "1 tbsp [olive oil]", "200 g [spaghetti], dried", "[parmesan]"]);
+        assert!(imported.review.iter().any(|r| r.starts_with("name:")));
+        assert!(imported.review.iter().any(|r| r.starts_with("cuisine: not given; choose from")));
+    }
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(2.0), "2");
+        assert_eq!(format_amount(0.5), "1/2");
+        assert_eq!(format_amount(1.75), "1 3/4");
+        assert_eq!(format_amount(0.3), "0.3");
+        assert_eq!(amount_value("1 1/2"), Some(1.5));
+        assert_eq!(amount_value("a pinch"), None);
+    }
+}
@@ -0,0 +1,298 @@
+//! Delta patches between `recipes.json` builds, so offline clients can catch up on what
+//! changed instead of downloading every recipe again.
+//!
+//! Each build diffs the previous `recipes.json` against the new one and writes
+//! `deltas/<old-version>-<new-version>.json`. Deltas from older versions to the previous build
+//! are composed with the new one, so clients up to `HISTORY` builds behind keep a patch to the
+//! current version; the manifest lists those old versions.
+
+use crate::cache::sha256_hex;
+use crate::{sort_newest_first, Recipe};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// How many old versions keep a delta to the current one.
+pub(crate) const HISTORY: usize = 10;
+
+/// The recipes added, removed and changed between two builds, identified by their version
+/// (the SHA-256 of `recipes.json`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct Delta {
+    pub(crate) from: String,
+    pub(crate) to: String,
+    pub(crate) added: Vec<Recipe>,
+    pub(crate) removed: Vec<String>,
+    pub(crate) changed: Vec<Recipe>,
+}
+
+enum Change {
+    Added(Recipe),
+    Changed(Recipe),
+    Removed,
+}
+
+impl Delta {
+    fn diff(old: &[Recipe], new: &[Recipe], from: &str, to: &str) -> Result<Self> {
+        let old: BTreeMap<&str, &Recipe> = old.iter().map(|r| (r.id.as_str(), r)).collect();
+        let mut changes = BTreeMap::new();
+        for recipe in new {
+            match old.get(recipe.id.as_str()) {
+                None => {
+                    changes.insert(recipe.id.clone(), Change::Added(recipe.clone()));
+                }
+                Some(previous) if serde_json::to_string(previous)? != serde_json::to_string(recipe)? => {
+                    changes.insert(recipe.id.clone(), Change::Changed(recipe.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+        for id in old.keys() {
+            if !new.iter().any(|r| r.id == *id) {
+                changes.insert(id.to_string(), Change::Removed);
+            }
+        }
+        Ok(Self::from_changes(from, to, changes))
+    }
+
+    fn from_changes(from: &str, to: &str, changes: BTreeMap<String, Change>) -> Self {
+        let mut delta = Self { from: from.to_string(), to: to.to_string(), added: Vec::new(), removed: Vec::new(), changed: Vec::new() };
+        for (id, change) in changes {
+            match change {
+                Change::Added(recipe) => delta.added.push(recipe),
+                Change::Changed(recipe) => delta.changed.push(recipe),
+                Change::Removed => delta.removed.push(id),
+            }
+        }
+        delta
+    }
+
+    fn into_changes(self) -> BTreeMap<String, Change> {
+        let mut changes = BTreeMap::new();
+        for recipe in self.added {
+            changes.insert(recipe.id.clone(), Change::Added(recipe));
+        }
+        for recipe in self.changed {
+            changes.insert(recipe.id.clone(), Change::Changed(recipe));
+        }
+        for id in self.removed {
+            changes.insert(id, Change::Removed);
+        }
+        changes
+    }
+
+    /// `self` (X→P) followed by `next` (P→N), as a single X→N delta.
+    fn then(self, next: Delta) -> Self {
+        let (from, to) = (self.from.clone(), next.to.clone());
+        let mut changes = self.into_changes();
+        for (id, change) in next.into_changes() {
+            let Some(earlier) = changes.remove(&id) else {
+                changes.insert(id, change);
+                continue;
+            };
+            // Whether the recipe exists in X decides between added and changed
+            let existed = !matches!(earlier, Change::Added(_));
+            let combined = match (existed, change) {
+                (true, Change::Removed) => Some(Change::Removed),
+                (false, Change::Removed) => None,
+                (true, Change::Added(recipe) | Change::Changed(recipe)) => Some(Change::Changed(recipe)),
+                (false, Change::Added(recipe) | Change::Changed(recipe)) => Some(Change::Added(recipe)),
+            };
+            if let Some(combined) = combined {
+                changes.insert(id, combined);
+            }
+        }
+        Self::from_changes(&from, &to, changes)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Apply the delta to the recipes of the `from` build, in `recipes.json` order.
+    pub(crate) fn apply(&self, old: Vec<Recipe>) -> Vec<Recipe> {
+        let mut recipes: Vec<Recipe> = old.into_iter()
+            .filter(|r| !self.removed.contains(&r.id) && !self.changed.iter().any(|c| c.id == r.id))
+            .chain(self.changed.iter().cloned())
+            .chain(self.added.iter().cloned())
+            .collect();
+        sort_newest_first(&mut recipes);
+        recipes
+    }
+}
+
+fn file_name(from: &str, to: &str) -> String {
+    format!("{}-{}.json", from, to)
+}
+
+/// Apply the delta to `old` and serialize the result exactly as `write_outputs` does, failing
+/// unless `old` is the `from` build and the result is the `to` build.
+pub(crate) fn verify(delta: &Delta, old: &str) -> Result<()> {
+    if sha256_hex(old.as_bytes()) != delta.from {
+        bail!("The old build has version {}, but the delta applies to {}", sha256_hex(old.as_bytes()), delta.from);
+    }
+    let recipes: Vec<Recipe> = serde_json::from_str(old).context("Failed to parse the old build")?;
+    let patched = serde_json::to_string_pretty(&delta.apply(recipes)).context("Failed to serialize the patched build")?;
+    let version = sha256_hex(patched.as_bytes());
+    if version != delta.to {
+        bail!("Applying the delta gives version {}, expected {}", version, delta.to);
+    }
+    Ok(())
+}
+
+/// Update `dir` after a build from `previous` (the old `recipes.json`, if any) to `json`.
+/// `listed` are the old versions the previous manifest had deltas for, newest first. Returns
+/// the old versions that now have a delta to the new build; every other file in `dir` is removed.
+pub(crate) fn update(dir: &Path, previous: Option<&str>, json: &str, recipes: &[Recipe], listed: &[String]) -> Result<Vec<String>> {
+    let version = sha256_hex(json.as_bytes());
+    let mut deltas = Vec::new();
+    match previous {
+        // Rebuilding the same recipes keeps the deltas that already lead to this version
+        Some(previous) if sha256_hex(previous.as_bytes()) == version => {
+            deltas = listed.iter()
+                .filter(|from| dir.join(file_name(from, &version)).exists())
+                .map(|from| (from.clone(), None))
+                .collect();
+        }
+        Some(previous) => match serde_json::from_str::<Vec<Recipe>>(previous) {
+            Ok(old) => {
+                let from = sha256_hex(previous.as_bytes());
+                let delta = Delta::diff(&old, recipes, &from, &version)?;
+                verify(&delta, previous).context("Delta from the previous build does not reproduce the new one")?;
+                for older in listed.iter().filter(|older| **older != version).take(HISTORY - 1) {
+                    let path = dir.join(file_name(older, &from));
+                    let Ok(contents) = fs::read_to_string(&path) else { continue };
+                    // Its clients download everything, as when their version has no delta
+                    let earlier: Delta = match serde_json::from_str(&contents) {
+                        Ok(earlier) => earlier,
+                        Err(e) => {
+                            eprintln!("⚠️  Skipped unreadable delta {:?}: {}", path, e);
+                            continue;
+                        }
+                    };
+                    let composed = earlier.then(delta.clone());
+                    if !composed.is_empty() {
+                        deltas.push((older.clone(), Some(composed)));
+                    }
+                }
+                deltas.insert(0, (from, Some(delta)));
+            }
+            // A previous build in an older shape can't be patched; clients download it all
+            Err(e) => eprintln!("⚠️  Previous build is not readable, no delta written: {}", e),
+        },
+        None => {}
+    }
+
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create delta directory: {:?}", dir))?;
+    for (from, delta) in &deltas {
+        if let Some(delta) = delta {
+            let path = dir.join(file_name(from, &version));
+            let contents = serde_json::to_string(delta).context("Failed to serialize delta")?;
+            crate::write_if_changed(&path, &contents).with_context(|| format!("Failed to write delta: {:?}", path))?;
+        }
+    }
+    let keep: Vec<String> = deltas.iter().map(|(from, _)| file_name(from, &version)).collect();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read delta directory: {:?}", dir))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.ends_with(".json") && !keep.contains(&name) {
+            fs::remove_file(entry.path()).with_context(|| format!("Failed to remove stale delta: {:?}", entry.path()))?;
+        }
+    }
+    Ok(deltas.into_iter().map(|(from, _)| from).collect())
+}
+
+/// The `verify-delta` subcommand: check that `delta` turns the `old` recipes.json into its
+/// `to` version.
+pub(crate) fn run(old: &Path, delta: &Path) -> Result<()> {
+    let old_json = fs::read_to_string(old).with_context(|| format!("Failed to read {:?}", old))?;
+    let delta_json = fs::read_to_string(delta).with_context(|| format!("Failed to read {:?}", delta))?;
+    let parsed: Delta = serde_json::from_str(&delta_json).with_context(|| format!("Failed to parse delta: {:?}", delta))?;
+    verify(&parsed, &old_json).with_context(|| format!("{:?} does not apply to {:?}", delta, old))?;
+    println!(
+        "✅ {:?} patches {:?} to version {} ({} added, {} removed, {} changed)",
+        delta, old, parsed.to, parsed.added.len(), parsed.removed.len(), parsed.changed.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::TestRecipe;
+    use crate::CanonicalData;
+
+    fn recipe(id: &str, time: u32) -> Recipe {
+        TestRecipe::new(id).set("time", time).steps("1. Chop the {onion}.").parse(&CanonicalData::empty())
+    }
+
+    fn build(mut recipes: Vec<Recipe>) -> (Vec<Recipe>, String) {
+        sort_newest_first(&mut recipes);
+        let json = serde_json::to_string_pretty(&recipes).unwrap();
+        (recipes, json)
+    }
+
+    #[test]
+    fn test_update_writes_and_composes_deltas() {
+        let dir = std::env::temp_dir().join(format!("recipe-parser-deltas-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let (_, first) = build(vec![recipe("dal", 30), recipe("soup", 20)]);
+        let (second_recipes, second) = build(vec![recipe("dal", 40), recipe("soup", 20), recipe("curry", 25)]);
+        let (third_recipes, third) = build(vec![recipe("dal", 40), recipe("curry", 35)]);
+
+        // No previous build, no delta
+        assert!(update(&dir, None, &first, &[], &[]).unwrap().is_empty());
+
+        let listed = update(&dir, Some(&first), &second, &second_recipes, &[]).unwrap();
+        assert_eq!(listed, vec![sha256_hex(first.as_bytes())]);
+        let path = dir.join(file_name(&sha256_hex(first.as_bytes()), &sha256_hex(second.as_bytes())));
+        let delta: Delta = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(delta.added.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["curry"]);
+        assert_eq!(delta.changed.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["dal"]);
+        assert!(delta.removed.is_empty());
+
+        // The first build's delta is composed with the next one: curry is still added, soup removed
+        let listed = update(&dir, Some(&second), &third, &third_recipes, &listed).unwrap();
+        assert_eq!(listed, vec![sha256_hex(second.as_bytes()), sha256_hex(first.as_bytes())]);
+        assert!(!path.exists());
+        let composed = fs::read_to_string(dir.join(file_name(&sha256_hex(first.as_bytes()), &sha256_hex(third.as_bytes())))).unwrap();
+        let composed: Delta = serde_json::from_str(&composed).unwrap();
+        verify(&composed, &first).unwrap();
+        assert_eq!(composed.added.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["curry"]);
+        assert_eq!(composed.removed, vec!["soup"]);
+
+        // Rebuilding without changes keeps the list
+        assert_eq!(update(&dir, Some(&third), &third, &third_recipes, &listed).unwrap(), listed);
+
+        // An unreadable older delta is skipped, not fatal
+        fs::write(dir.join(file_name(&sha256_hex(first.as_bytes()), &sha256_hex(third.as_bytes()))), "{").unwrap();
+        let (fourth_recipes, fourth) = build(vec![recipe("dal", 45), recipe("curry", 35)]);
+        let listed = update(&dir, Some(&third), &fourth, &fourth_recipes, &listed).unwrap();
+        assert_eq!(listed, vec![sha256_hex(third.as_bytes()), sha256_hex(second.as_bytes())]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_builds() {
+        let (old_recipes, old) = build(vec![recipe("dal", 30)]);
+        let (new_recipes, new) = build(vec![recipe("dal", 40)]);
+        let delta = Delta::diff(&old_recipes, &new_recipes, &sha256_hex(old.as_bytes()), &sha256_hex(new.as_bytes())).unwrap();
+        verify(&delta, &old).unwrap();
+        assert!(verify(&delta, &new).unwrap_err().to_string().contains("delta applies to"));
+
+        let wrong = Delta { to: sha256_hex(old.as_bytes()), ..delta };
+        assert!(verify(&wrong, &old).unwrap_err().to_string().contains("expected"));
+    }
+
+    #[test]
+    fn test_real_recipes_round_trip() {
+        // Deltas rely on recipes.json deserializing back into identical bytes
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../docs/recipes.json");
+        let json = fs::read_to_string(path).unwrap();
+        let recipes: Vec<Recipe> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&recipes).unwrap(), json);
+    }
+}
@@ -0,0 +1,476 @@
+//! Lint diagnostics collected while parsing recipes.
+//!
+//! Checks push into a `Diagnostics` sink instead of bailing, so a single run
+//! reports every problem in every file. Diagnostics may carry a byte span into
+//! the recipe source, which is rendered rustc-style with the offending line,
+//! or as JSON, SARIF or GitHub workflow commands for tooling.
+
+use serde::Serialize;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// How lint diagnostics are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Format {
+    /// Human-readable, rustc-style snippets on stderr
+    Text,
+    /// A JSON document on stdout
+    Json,
+    /// A SARIF 2.1.0 log on stdout, for code scanning tools
+    Sarif,
+    /// GitHub Actions workflow commands on stdout, for inline PR annotations
+    Github,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Diagnostic {
+    pub(crate) severity: Severity,
+    /// Stable kebab-case identifier of the check that fired, e.g. `unit-spacing`.
+    pub(crate) rule: &'static str,
+    pub(crate) message: String,
+    /// Byte range into the recipe source, if the problem can be located.
+    pub(crate) span: Option<Range<usize>>,
+    /// Edits that resolve this diagnostic, applied by `--fix`.
+    pub(crate) fix: Option<Fix>,
+}
+
+impl Diagnostic {
+    pub(crate) fn with_fix(&mut self, label: impl Into<String>, edits: Vec<Edit>) {
+        self.fix = Some(Fix { label: label.into(), edits });
+    }
+}
+
+/// Replace the bytes at `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Edit {
+    pub(crate) span: Range<usize>,
+    pub(crate) replacement: String,
+}
+
+/// A machine-applicable fix: the edits plus a short description for the `--fix` report.
+#[derive(Debug, Clone)]
+pub(crate) struct Fix {
+    pub(crate) label: String,
+    pub(crate) edits: Vec<Edit>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub(crate) fn error(&mut self, rule: &'static str, span: Option<Range<usize>>, message: impl Into<String>) -> &mut Diagnostic {
+        self.push(Severity::Error, rule, span, message.into())
+    }
+
+    pub(crate) fn warning(&mut self, rule: &'static str, span: Option<Range<usize>>, message: impl Into<String>) -> &mut Diagnostic {
+        self.push(Severity::Warning, rule, span, message.into())
+    }
+
+    fn push(&mut self, severity: Severity, rule: &'static str, span: Option<Range<usize>>, message: String) -> &mut Diagnostic {
+        self.items.push(Diagnostic { severity, rule, message, span, fix: None });
+        self.items.last_mut().expect("just pushed")
+    }
+
+    pub(crate) fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    pub(crate) fn error_count(&self) -> usize {
+        self.items.iter().filter(|d| d.severity == Severity::Error).count()
+    }
+
+    pub(crate) fn warning_count(&self) -> usize {
+        self.items.iter().filter(|d| d.severity == Severity::Warning).count()
+    }
+
+    pub(crate) fn fixable_count(&self) -> usize {
+        self.items.iter().filter(|d| d.fix.is_some()).count()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter()
+    }
+}
+
+/// Span of the first occurrence of `needle` inside `span` (ASCII case-insensitive).
+pub(crate) fn find_span(source: &str, span: &Range<usize>, needle: &str) -> Option<Range<usize>> {
+    if needle.is_empty() {
+        return None;
+    }
+    let haystack = source.get(span.clone())?.to_ascii_lowercase();
+    let pos = haystack.find(&needle.to_ascii_lowercase())?;
+    Some(span.start + pos..span.start + pos + needle.len())
+}
+
+/// Like `find_span`, but only matches `needle` exactly (case included), for spans that
+/// are about to be rewritten.
+pub(crate) fn find_exact(source: &str, span: Option<&Range<usize>>, needle: &str) -> Option<Range<usize>> {
+    let span = span?;
+    let pos = source.get(span.clone())?.find(needle)?;
+    (!needle.is_empty()).then(|| span.start + pos..span.start + pos + needle.len())
+}
+
+/// Narrow `span` to the first occurrence of `needle` inside it, falling back to the
+/// whole span when the needle can't be found.
+pub(crate) fn narrow_span(source: &str, span: Option<&Range<usize>>, needle: &str) -> Option<Range<usize>> {
+    let span = span?;
+    find_span(source, span, needle).or_else(|| Some(span.clone()))
+}
+
+/// 1-based line and column (in characters) of a byte offset.
+pub(crate) fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = source[line_start..offset].chars().count() + 1;
+    (line, column)
+}
+
+/// Diagnostics for one recipe file, with the source they point into.
+#[derive(Debug, Clone)]
+pub(crate) struct FileReport {
+    pub(crate) file: String,
+    pub(crate) source: String,
+    pub(crate) diagnostics: Diagnostics,
+}
+
+/// Render one diagnostic rustc-style: header, location, source line and caret underline.
+/// Continuation lines of the message are shown as `=` notes below the snippet.
+fn render(file: &str, source: &str, d: &Diagnostic) -> String {
+    let mut lines = d.message.lines();
+    let mut out = format!("{}[{}]: {}\n", d.severity.as_str(), d.rule, lines.next().unwrap_or_default());
+
+    match &d.span {
+        Some(span) => {
+            let (line, column) = line_col(source, span.start);
+            let gutter = " ".repeat(line.to_string().len());
+            let text = source.lines().nth(line - 1).unwrap_or_default();
+            // Underline up to the end of the span or of the first line, at least one caret
+            let remaining = text.chars().count().saturating_sub(column - 1);
+            let span_chars = source.get(span.clone()).map(|s| s.chars().count()).unwrap_or(1);
+            let carets = span_chars.min(remaining).max(1);
+            out.push_str(&format!("{}--> {}:{}:{}\n", gutter, file, line, column));
+            out.push_str(&format!("{} |\n", gutter));
+            out.push_str(&format!("{} | {}\n", line, text));
+            out.push_str(&format!("{} | {}{}\n", gutter, " ".repeat(column - 1), "^".repeat(carets)));
+            for note in lines {
+                out.push_str(&format!("{} = {}\n", gutter, note.trim()));
+            }
+        }
+        None => {
+            out.push_str(&format!(" --> {}\n", file));
+            for note in lines {
+                out.push_str(&format!("  = {}\n", note.trim()));
+            }
+        }
+    }
+    out
+}
+
+/// Every error in `reports`, rendered as in text output, for showing outside the terminal.
+pub(crate) fn render_errors(reports: &[FileReport]) -> String {
+    reports.iter()
+        .flat_map(|report| report.diagnostics.iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| render(&report.file, &report.source, d)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A diagnostic resolved to file coordinates, as emitted by the machine-readable formats.
+/// Lines and columns are 1-based; `end_column` is exclusive.
+#[derive(Debug, Serialize)]
+struct LocatedDiagnostic<'a> {
+    rule: &'static str,
+    severity: Severity,
+    file: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_column: Option<usize>,
+    message: String,
+    fixable: bool,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    errors: usize,
+    warnings: usize,
+    diagnostics: Vec<LocatedDiagnostic<'a>>,
+}
+
+fn locate(reports: &[FileReport]) -> Vec<LocatedDiagnostic<'_>> {
+    let mut located = Vec::new();
+    for report in reports {
+        for d in report.diagnostics.iter() {
+            let start = d.span.as_ref().map(|s| line_col(&report.source, s.start));
+            let end = d.span.as_ref().map(|s| line_col(&report.source, s.end));
+            located.push(LocatedDiagnostic {
+                rule: d.rule,
+                severity: d.severity,
+                file: &report.file,
+                line: start.map(|(line, _)| line),
+                column: start.map(|(_, column)| column),
+                end_line: end.map(|(line, _)| line),
+                end_column: end.map(|(_, column)| column),
+                // Collapse the indented hint lines into one line
+                message: d.message.lines().map(str::trim).collect::<Vec<_>>().join(" "),
+                fixable: d.fix.is_some(),
+            });
+        }
+    }
+    located
+}
+
+fn to_sarif(located: &[LocatedDiagnostic]) -> serde_json::Value {
+    let mut rule_ids: Vec<&str> = located.iter().map(|d| d.rule).collect();
+    rule_ids.sort();
+    rule_ids.dedup();
+
+    let results: Vec<serde_json::Value> = located.iter().map(|d| {
+        let mut region = serde_json::Map::new();
+        if let (Some(line), Some(column)) = (d.line, d.column) {
+            region.insert("startLine".into(), line.into());
+            region.insert("startColumn".into(), column.into());
+        }
+        if let (Some(line), Some(column)) = (d.end_line, d.end_column) {
+            region.insert("endLine".into(), line.into());
+            region.insert("endColumn".into(), column.into());
+        }
+        let mut location = serde_json::json!({
+            "physicalLocation": { "artifactLocation": { "uri": d.file } }
+        });
+        if !region.is_empty() {
+            location["physicalLocation"]["region"] = region.into();
+        }
+        serde_json::json!({
+            "ruleId": d.rule,
+            "level": d.severity.as_str(),
+            "message": { "text": d.message },
+            "locations": [location],
+        })
+    }).collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "recipe-parser",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rule_ids.iter().map(|id| serde_json::json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// Escape workflow command data (`%`, CR, LF) and, for properties, `:` and `,`.
+fn github_escape(s: &str, property: bool) -> String {
+    let s = s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    if property {
+        s.replace(':', "%3A").replace(',', "%2C")
+    } else {
+        s
+    }
+}
+
+fn to_github(d: &LocatedDiagnostic) -> String {
+    let mut properties = vec![format!("file={}", github_escape(d.file, true))];
+    if let (Some(line), Some(column)) = (d.line, d.column) {
+        properties.push(format!("line={}", line));
+        properties.push(format!("col={}", column));
+    }
+    if let (Some(line), Some(column)) = (d.end_line, d.end_column) {
+        properties.push(format!("endLine={}", line));
+        properties.push(format!("endColumn={}", column));
+    }
+    properties.push(format!("title={}", github_escape(d.rule, true)));
+    format!("::{} {}::{}", d.severity.as_str(), properties.join(","), github_escape(&d.message, false))
+}
+
+/// Print every diagnostic in the requested format. Text output is grouped by file on stderr
+/// and followed by a one-line summary; the other formats write to stdout.
+pub(crate) fn print_reports(reports: &[FileReport], format: Format) {
+    let errors: usize = reports.iter().map(|r| r.diagnostics.error_count()).sum();
+    let warnings: usize = reports.iter().map(|r| r.diagnostics.warning_count()).sum();
+
+    match format {
+        Format::Text => {
+            for report in reports {
+                for d in report.diagnostics.iter() {
+                    eprintln!("{}", render(&report.file, &report.source, d));
+                }
+            }
+            let files = reports.iter().filter(|r| !r.diagnostics.is_empty()).count();
+            if errors > 0 {
+                eprintln!("❌ Found {} error(s) and {} warning(s) in {} file(s)", errors, warnings, files);
+            } else if warnings > 0 {
+                eprintln!("⚠️  Found {} warning(s) in {} file(s)", warnings, files);
+            }
+            let fixable: usize = reports.iter().map(|r| r.diagnostics.fixable_count()).sum();
+            if fixable > 0 {
+                eprintln!("🔧 {} of these can be fixed automatically with --fix", fixable);
+            }
+        }
+        Format::Json => {
+            let report = JsonReport { errors, warnings, diagnostics: locate(reports) };
+            println!("{}", serde_json::to_string_pretty(&report).expect("diagnostics serialize"));
+        }
+        Format::Sarif => {
+            let sarif = to_sarif(&locate(reports));
+            println!("{}", serde_json::to_string_pretty(&sarif).expect("SARIF serializes"));
+        }
+        Format::Github => {
+            for d in locate(reports) {
+                println!("{}", to_github(&d));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_by_severity() {
+        let mut diagnostics = Diagnostics::default();
+        assert!(diagnostics.is_empty());
+        diagnostics.error("rule-a", None, "first");
+        diagnostics.warning("rule-b", None, "second");
+        diagnostics.error("rule-a", None, "third");
+        assert!(diagnostics.has_errors());
+        assert_eq!(diagnostics.error_count(), 2);
+        assert_eq!(diagnostics.warning_count(), 1);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn test_line_col() {
+        let source = "---\nid: x\n---\n- ½ tsp [salt]\n";
+        assert_eq!(line_col(source, 0), (1, 1));
+        assert_eq!(line_col(source, 4), (2, 1));
+        let salt = source.find("[salt]").unwrap();
+        // '½' is two bytes but one column
+        assert_eq!(line_col(source, salt), (4, 9));
+    }
+
+    #[test]
+    fn test_narrow_span() {
+        let source = "- 500g [Garlic]";
+        let whole = 2..source.len();
+        assert_eq!(narrow_span(source, Some(&whole), "500g"), Some(2..6));
+        assert_eq!(narrow_span(source, Some(&whole), "garlic"), Some(8..14));
+        assert_eq!(narrow_span(source, Some(&whole), "onion"), Some(whole.clone()));
+        assert_eq!(narrow_span(source, None, "500g"), None);
+        assert_eq!(find_span(source, &whole, "onion"), None);
+    }
+
+    #[test]
+    fn test_render_with_snippet() {
+        let source = "# Ingredients\n\n- 1 tbsp olive oil\n";
+        let start = source.find("1 tbsp").unwrap();
+        let d = Diagnostic {
+            severity: Severity::Error,
+            rule: "missing-canonical",
+            fix: None,
+            message: "Ingredient is missing a [canonical] tag.\n  Wrap it in brackets.".to_string(),
+            span: Some(start..start + "1 tbsp olive oil".len()),
+        };
+        let rendered = render("recipes/x.md", source, &d);
+        assert_eq!(
+            rendered,
+            "error[missing-canonical]: Ingredient is missing a [canonical] tag.\n \
+             --> recipes/x.md:3:3\n  \
+             |\n\
+             3 | - 1 tbsp olive oil\n  \
+             |   ^^^^^^^^^^^^^^^^\n  \
+             = Wrap it in brackets.\n"
+        );
+    }
+
+    fn sample_reports() -> Vec<FileReport> {
+        let source = "---\nid: x\n---\n- 500g [flour]\n".to_string();
+        let start = source.find("500g").unwrap();
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.error("unit-spacing", Some(start..start + 4), "'500g' should be '500 g'.\n  Add a space.");
+        diagnostics.warning("unreferenced-ingredient", None, "Ingredient 'flour' is not linked, 100%");
+        vec![FileReport { file: "recipes/x.md".to_string(), source, diagnostics }]
+    }
+
+    #[test]
+    fn test_json_locates_diagnostics() {
+        let reports = sample_reports();
+        let json = serde_json::to_value(JsonReport { errors: 1, warnings: 1, diagnostics: locate(&reports) }).unwrap();
+        assert_eq!(json["diagnostics"][0], serde_json::json!({
+            "rule": "unit-spacing",
+            "severity": "error",
+            "file": "recipes/x.md",
+            "line": 4,
+            "column": 3,
+            "end_line": 4,
+            "end_column": 7,
+            "message": "'500g' should be '500 g'. Add a space.",
+            "fixable": false,
+        }));
+        assert_eq!(json["diagnostics"][1]["severity"], "warning");
+        assert!(json["diagnostics"][1].get("line").is_none());
+    }
+
+    #[test]
+    fn test_sarif_results() {
+        let reports = sample_reports();
+        let sarif = to_sarif(&locate(&reports));
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "unit-spacing");
+        assert_eq!(result["level"], "error");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "recipes/x.md");
+        assert_eq!(location["region"]["startLine"], 4);
+        assert_eq!(location["region"]["startColumn"], 3);
+        assert!(run["results"][1]["locations"][0]["physicalLocation"].get("region").is_none());
+    }
+
+    #[test]
+    fn test_github_workflow_commands() {
+        let reports = sample_reports();
+        let lines: Vec<String> = locate(&reports).iter().map(to_github).collect();
+        assert_eq!(lines, vec![
+            "::error file=recipes/x.md,line=4,col=3,endLine=4,endColumn=7,title=unit-spacing::'500g' should be '500 g'. Add a space.",
+            "::warning file=recipes/x.md,title=unreferenced-ingredient::Ingredient 'flour' is not linked, 100%25",
+        ]);
+    }
+}
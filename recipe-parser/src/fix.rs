@@ -0,0 +1,156 @@
+//! `--fix`: apply the edits attached to autofixable lint diagnostics (unit spacing,
+//! unicode fractions, copy-pasted indentation, legacy `## Section` headers).
+//! Everything outside the edited spans is left byte-for-byte as it was.
+
+use crate::diagnostics::{self, Diagnostics, Edit};
+use crate::{parse_recipe, CanonicalData};
+use similar::TextDiff;
+
+/// Fixes can unmask each other (indentation stops parsing before anything else is checked),
+/// so the source is re-linted after each round. This bounds the number of rounds.
+const MAX_PASSES: usize = 5;
+
+/// One fix applied to a file, for the `--fix` report.
+#[derive(Debug)]
+pub(crate) struct AppliedFix {
+    pub(crate) rule: &'static str,
+    /// 1-based line in the source the fix was computed against.
+    pub(crate) line: usize,
+    pub(crate) label: String,
+}
+
+/// Lint `source` and apply every available fix until none are left.
+/// Returns the fixed source and what was changed.
+pub(crate) fn fix_source(source: &str, canonical: &CanonicalData) -> (String, Vec<AppliedFix>) {
+    let mut current = source.to_string();
+    let mut applied = Vec::new();
+
+    for _ in 0..MAX_PASSES {
+        let mut diagnostics = Diagnostics::default();
+        parse_recipe(&current, true, canonical, &mut diagnostics);
+
+        let mut edits = Vec::new();
+        for d in diagnostics.iter() {
+            let Some(fix) = &d.fix else { continue };
+            let Some(first) = fix.edits.iter().map(|e| e.span.start).min() else { continue };
+            // Several diagnostics can point at the same text (e.g. "500g" twice in one line);
+            // only the first claims it, the rest are picked up by the next pass.
+            if fix.edits.iter().any(|e| edits.iter().any(|o: &Edit| overlaps(o, e))) {
+                continue;
+            }
+            edits.extend(fix.edits.iter().cloned());
+            applied.push(AppliedFix {
+                rule: d.rule,
+                line: diagnostics::line_col(&current, first).0,
+                label: fix.label.clone(),
+            });
+        }
+        if edits.is_empty() {
+            break;
+        }
+        current = apply_edits(&current, edits);
+    }
+
+    (current, applied)
+}
+
+fn overlaps(a: &Edit, b: &Edit) -> bool {
+    a.span.start < b.span.end.max(b.span.start + 1) && b.span.start < a.span.end.max(a.span.start + 1)
+}
+
+/// Apply non-overlapping edits to `source`.
+fn apply_edits(source: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|e| e.span.start);
+    let mut out = String::with_capacity(source.len());
+    let mut cursor = 0;
+    for edit in edits {
+        out.push_str(&source[cursor..edit.span.start]);
+        out.push_str(&edit.replacement);
+        cursor = edit.span.end;
+    }
+    out.push_str(&source[cursor..]);
+    out
+}
+
+/// Unified diff between the original and fixed source, for `--fix --dry-run`.
+pub(crate) fn unified_diff(file: &str, old: &str, new: &str) -> String {
+    let file = file.trim_start_matches("./").trim_start_matches('/');
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&format!("a/{}", file), &format!("b/{}", file))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::TestRecipe;
+
+    /// Breaks every autofixable rule: a section header, unit spacing and unicode fractions.
+    fn recipe() -> String {
+        TestRecipe::new("test-recipe")
+            .ingredients("## Fresh\n\n- 500g [flour]\n- 1½ tbsp [olive oil]\n- ¼ tsp [salt]")
+            .steps("1. Mix {flour}, {olive oil} and {salt}.")
+            .source()
+    }
+
+    #[test]
+    fn test_fixes_every_autofixable_error() {
+        let (fixed, applied) = fix_source(&recipe(), &CanonicalData::empty());
+        assert!(fixed.contains("\n# Ingredients\n\n- 500 g [flour]\n- 1-1/2 tbsp [olive oil]\n- 1/4 tsp [salt]\n"));
+        assert!(!fixed.contains("## Fresh"));
+
+        let mut labels: Vec<(&str, &str)> = applied.iter().map(|a| (a.rule, a.label.as_str())).collect();
+        labels.sort();
+        assert_eq!(labels, vec![
+            ("section-header", "removed '## Fresh'"),
+            ("unicode-fraction", "'¼' → '1/4'"),
+            ("unicode-fraction", "'½' → '-1/2'"),
+            ("unit-spacing", "'500g' → '500 g'"),
+        ]);
+
+        let mut diagnostics = Diagnostics::default();
+        assert!(parse_recipe(&fixed, true, &CanonicalData::empty(), &mut diagnostics).is_some());
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_leaves_everything_else_untouched() {
+        let (fixed, _) = fix_source(&recipe(), &CanonicalData::empty());
+        let expected = recipe()
+            .replace("## Fresh\n\n", "")
+            .replace("500g", "500 g")
+            .replace("1½", "1-1/2")
+            .replace("¼", "1/4");
+        assert_eq!(fixed, expected);
+    }
+
+    #[test]
+    fn test_fixes_indentation_then_content() {
+        let indented: String = recipe().lines().map(|l| if l.is_empty() { "\n".to_string() } else { format!("    {}\n", l) }).collect();
+        let (fixed, applied) = fix_source(&indented, &CanonicalData::empty());
+        assert_eq!(applied[0].rule, "indentation");
+        assert_eq!(fixed, fix_source(&recipe(), &CanonicalData::empty()).0);
+    }
+
+    #[test]
+    fn test_repeated_occurrences_are_all_fixed() {
+        let source = recipe().replace("- 500g [flour]", "- 500g [flour] (or 500g rye)");
+        let (fixed, _) = fix_source(&source, &CanonicalData::empty());
+        assert!(fixed.contains("- 500 g [flour] (or 500 g rye)"));
+    }
+
+    #[test]
+    fn test_clean_recipe_is_unchanged() {
+        let (fixed, _) = fix_source(&recipe(), &CanonicalData::empty());
+        let (again, applied) = fix_source(&fixed, &CanonicalData::empty());
+        assert_eq!(again, fixed);
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("recipes/x.md", "a\n500g\nb\n", "a\n500 g\nb\n");
+        assert_eq!(diff, "--- a/recipes/x.md\n+++ b/recipes/x.md\n@@ -1,3 +1,3 @@\n a\n-500g\n+500 g\n b\n");
+    }
+}
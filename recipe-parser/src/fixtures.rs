@@ -0,0 +1,77 @@
+//! Fixtures for the unit tests: a small vocabulary, and recipes with valid frontmatter whose
+//! defaults a test overrides where it matters, with its own ingredient lines and steps.
+
+use crate::{parse_recipe, CanonicalData, Diagnostics, Recipe};
+
+/// `CanonicalData::empty()` with `ingredients` as (name, section) and `plurals` as (plural, name).
+pub(crate) fn vocabulary(ingredients: &[(&str, &str)], plurals: &[(&str, &str)]) -> CanonicalData {
+    let mut data = CanonicalData::empty();
+    for (name, section) in ingredients {
+        data.ingredients.insert(name.to_string(), name.to_string());
+        data.ingredient_sections.insert(name.to_string(), section.to_string());
+    }
+    for (plural, name) in plurals {
+        data.ingredients.insert(plural.to_string(), name.to_string());
+        data.ingredient_plurals.insert(name.to_string(), plural.to_string());
+    }
+    data
+}
+
+pub(crate) struct TestRecipe {
+    frontmatter: Vec<(&'static str, String)>,
+    ingredients: String,
+    steps: String,
+}
+
+impl TestRecipe {
+    pub(crate) fn new(id: &str) -> Self {
+        let frontmatter = [
+            ("id", id.to_string()),
+            ("name", format!("Recipe {}", id)),
+            ("description", "A recipe used in tests".to_string()),
+            ("servings", "2".to_string()),
+            ("time", "10".to_string()),
+            ("difficulty", "easy".to_string()),
+            ("diet", "[vegan]".to_string()),
+            ("cuisine", "[french]".to_string()),
+            ("meal_type", "[dinner]".to_string()),
+            ("date", "2025-01-01".to_string()),
+        ];
+        Self {
+            frontmatter: frontmatter.to_vec(),
+            ingredients: "- 1 [onion]".to_string(),
+            steps: "1. Cook everything.".to_string(),
+        }
+    }
+
+    /// Replace a frontmatter field, or add one that has no default (e.g. `tested`).
+    pub(crate) fn set(mut self, key: &'static str, value: impl ToString) -> Self {
+        match self.frontmatter.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.frontmatter.push((key, value.to_string())),
+        }
+        self
+    }
+
+    /// Ingredient lines, one `- ...` per line.
+    pub(crate) fn ingredients(mut self, lines: &str) -> Self {
+        self.ingredients = lines.to_string();
+        self
+    }
+
+    /// Numbered instruction lines.
+    pub(crate) fn steps(mut self, lines: &str) -> Self {
+        self.steps = lines.to_string();
+        self
+    }
+
+    pub(crate) fn source(&self) -> String {
+        let frontmatter: String = self.frontmatter.iter().map(|(key, value)| format!("{}: {}\n", key, value)).collect();
+        format!("---\n{}---\n\n# Ingredients\n\n{}\n\n# Instructions\n\n{}\n", frontmatter, self.ingredients, self.steps)
+    }
+
+    /// Parse without linting; panics if the recipe doesn't parse.
+    pub(crate) fn parse(&self, canonical: &CanonicalData) -> Recipe {
+        parse_recipe(&self.source(), false, canonical, &mut Diagnostics::default()).unwrap()
+    }
+}
@@ -0,0 +1,361 @@
+//! `fmt`: rewrite recipe markdown into the house layout.
+//!
+//! - frontmatter keys in the standard order, lists in flow style (`diet: [vegan, gluten-free]`)
+//! - `# Section` headings separated by exactly one blank line
+//! - `- ` ingredient bullets, with groups separated by a single blank line
+//! - steps numbered `1.`, `2.`, … with no blank lines between them
+//! - no trailing whitespace, LF line endings, one final newline
+
+use crate::diagnostics::Diagnostics;
+use crate::{fix, parse_recipe, CanonicalData};
+use anyhow::{bail, Context, Result};
+use serde_yaml::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// Key order used by every recipe (see documentation/RECIPE_FORMAT.md), matching
+/// `RecipeFrontmatter`. Unknown keys follow in their original order.
+const FRONTMATTER_ORDER: [&str; 11] = [
+    "id", "name", "description", "servings", "time", "difficulty", "diet", "cuisine", "meal_type", "date", "tested",
+];
+
+/// Format a recipe. Fails if the source has no frontmatter, or if formatting would change
+/// what the parser reads from it.
+pub(crate) fn format_recipe(source: &str, canonical: &CanonicalData) -> Result<String> {
+    let source = source.replace("\r\n", "\n");
+    let parts: Vec<&str> = source.splitn(3, "---").collect();
+    if parts.len() < 3 || !parts[0].trim().is_empty() {
+        bail!("Invalid recipe format: missing frontmatter delimiters");
+    }
+
+    let mut out = String::from("---\n");
+    for entry in format_frontmatter(parts[1])? {
+        out.push_str(&entry);
+        out.push('\n');
+    }
+    out.push_str("---\n");
+    out.push_str(&format_body(parts[2]));
+
+    // Formatting must only ever change layout
+    let before = parsed(&source, canonical);
+    if before.is_some() && before != parsed(&out, canonical) {
+        bail!("Formatting would change the parsed recipe; please fix it by hand");
+    }
+    Ok(out)
+}
+
+/// Format each file in place, or with `check` just report the ones that would change
+/// (with a diff). Returns false if any file is unformatted (in check mode) or can't be formatted.
+pub(crate) fn run(files: &[PathBuf], check: bool, canonical: &CanonicalData) -> Result<bool> {
+    let mut changed = 0;
+    let mut failed = 0;
+    for path in files {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {:?}", path))?;
+        let formatted = match format_recipe(&source, canonical) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("❌ {}: {}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        changed += 1;
+        if check {
+            eprintln!("❌ Not formatted: {}", path.display());
+            print!("{}", fix::unified_diff(&path.display().to_string(), &source, &formatted));
+        } else {
+            fs::write(path, &formatted)
+                .with_context(|| format!("Failed to write formatted recipe: {:?}", path))?;
+            println!("  ✨ Formatted: {}", path.display());
+        }
+    }
+
+    if check && changed > 0 {
+        eprintln!("❌ {} of {} recipe(s) need formatting; run `recipe-parser fmt`", changed, files.len());
+    } else if check {
+        println!("✅ {} recipe(s) already formatted", files.len() - failed);
+    } else {
+        println!("✨ Formatted {} of {} recipe(s)", changed, files.len());
+    }
+    Ok(failed == 0 && !(check && changed > 0))
+}
+
+fn parsed(source: &str, canonical: &CanonicalData) -> Option<serde_json::Value> {
+    let recipe = parse_recipe(source, false, canonical, &mut Diagnostics::default())?;
+    serde_json::to_value(recipe).ok()
+}
+
+/// Split the frontmatter into top-level entries (a `key:` line plus any continuation lines),
+/// format each one and sort them into house order.
+fn format_frontmatter(text: &str) -> Result<Vec<String>> {
+    let mut entries: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        let starts_entry = !line.starts_with([' ', '-', '#']) && line.contains(':');
+        match entries.last_mut() {
+            _ if starts_entry => {
+                let key = line.split(':').next().unwrap_or_default().trim().to_string();
+                entries.push((key, vec![line]));
+            }
+            Some((_, lines)) => lines.push(line),
+            None if line.is_empty() => {}
+            None => bail!("Unexpected frontmatter line before the first key: '{}'", line),
+        }
+    }
+
+    let rank = |key: &str| FRONTMATTER_ORDER.iter().position(|k| *k == key).unwrap_or(FRONTMATTER_ORDER.len());
+    entries.sort_by_key(|(key, _)| rank(key));
+    entries.iter().map(|(key, lines)| format_entry(key, lines)).collect()
+}
+
+fn format_entry(key: &str, lines: &[&str]) -> Result<String> {
+    let mut lines = lines.to_vec();
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    let yaml = lines.join("\n");
+    let value = match serde_yaml::from_str::<serde_yaml::Mapping>(&yaml) {
+        Ok(mapping) => mapping.into_iter().next().map(|(_, v)| v),
+        Err(e) => bail!("Invalid frontmatter entry '{}': {}", key, e),
+    };
+
+    if let Some(Value::Sequence(items)) = &value {
+        let items: Option<Vec<String>> = items.iter().map(flow_item).collect();
+        if let Some(items) = items {
+            return Ok(format!("{}: [{}]", key, items.join(", ")));
+        }
+    }
+    if let [line] = lines[..] {
+        let value = line.split_once(':').map(|(_, v)| v.trim()).unwrap_or_default();
+        return Ok(if value.is_empty() { format!("{}:", key) } else { format!("{}: {}", key, value) });
+    }
+    // Block scalars and nested mappings are left alone
+    Ok(yaml)
+}
+
+/// A scalar written as a flow-list item, quoted only when it would otherwise read differently.
+fn flow_item(value: &Value) -> Option<String> {
+    let plain = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => return Some(n.to_string()),
+        Value::Bool(b) => return Some(b.to_string()),
+        _ => return None,
+    };
+    let reparsed: Option<Vec<Value>> = serde_yaml::from_str(&format!("[{}]", plain)).ok();
+    if reparsed.as_deref() == Some(std::slice::from_ref(value)) {
+        Some(plain)
+    } else {
+        serde_json::to_string(&plain).ok()
+    }
+}
+
+fn format_body(body: &str) -> String {
+    let mut preamble: Vec<&str> = Vec::new();
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in body.lines().map(str::trim_end) {
+        if let Some(title) = h1_title(line) {
+            sections.push((title.to_string(), Vec::new()));
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(line);
+        } else {
+            preamble.push(line);
+        }
+    }
+
+    let mut out = String::new();
+    let preamble = collapse_blank_lines(preamble);
+    if !preamble.is_empty() {
+        out.push('\n');
+        out.push_str(&preamble.join("\n"));
+        out.push('\n');
+    }
+    for (title, lines) in sections {
+        let lines = match title.as_str() {
+            "Ingredients" => format_ingredients(lines),
+            "Instructions" => format_instructions(lines),
+            _ => collapse_blank_lines(lines),
+        };
+        out.push_str(&format!("\n# {}\n", title));
+        if !lines.is_empty() {
+            out.push('\n');
+            out.push_str(&lines.join("\n"));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// `# Title` (but not `##`), with any amount of space after the hash.
+fn h1_title(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix('#')?;
+    if !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let title = rest.trim();
+    (!title.is_empty()).then_some(title)
+}
+
+/// Drop leading and trailing blank lines and squeeze runs of blank lines into one.
+fn collapse_blank_lines<S: AsRef<str>>(lines: Vec<S>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for line in lines {
+        let line = line.as_ref();
+        if line.is_empty() && out.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        out.push(line.to_string());
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out
+}
+
+fn format_ingredients(lines: Vec<&str>) -> Vec<String> {
+    let lines = lines.into_iter().map(|line| {
+        match line.strip_prefix(['-', '*', '+']) {
+            Some(rest) if rest.starts_with([' ', '\t']) => format!("- {}", rest.trim_start()),
+            _ => line.to_string(),
+        }
+    }).collect();
+    collapse_blank_lines(lines)
+}
+
+/// Leading `12.` or `12)` step number, returning the step text.
+fn step_text(line: &str) -> Option<&str> {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    let rest = line[digits..].strip_prefix(['.', ')'])?;
+    rest.starts_with([' ', '\t']).then(|| rest.trim_start())
+}
+
+fn format_instructions(lines: Vec<&str>) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut step = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            // Steps form one tight list
+            let next = lines[i + 1..].iter().find(|l| !l.is_empty());
+            if step > 0 && next.is_some_and(|l| step_text(l).is_some()) {
+                continue;
+            }
+            out.push(String::new());
+        } else if let Some(text) = step_text(line) {
+            step += 1;
+            out.push(format!("{}. {}", step, text));
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    collapse_blank_lines(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATTED: &str = "---
+id: test-recipe
+name: Test Recipe
+description: A recipe used to exercise the formatter
+servings: 2
+time: 30
+difficulty: easy
+diet: [vegan, gluten-free]
+cuisine: [british]
+meal_type: [dinner]
+date: 2025-01-01
+---
+
+# Notes
+
+Some notes.
+
+# Ingredients
+
+- 1 [onion], diced
+- 2 cloves [garlic]
+
+- 400 g [chopped tomatoes]
+
+# Instructions
+
+1. Fry the {onion} and {garlic}.
+2. Add the {chopped tomatoes}.
+
+# Serving Suggestions
+
+With bread.
+";
+
+    #[test]
+    fn test_formatted_recipe_is_unchanged() {
+        assert_eq!(format_recipe(FORMATTED, &CanonicalData::empty()).unwrap(), FORMATTED);
+    }
+
+    #[test]
+    fn test_formats_messy_recipe() {
+        let messy = "---\r
+date: 2025-01-01\r
+name:   Test Recipe\r
+id: test-recipe\r
+description: A recipe used to exercise the formatter\r
+servings: 2\r
+time: 30\r
+difficulty: easy\r
+diet:\r
+  - vegan\r
+  - gluten-free\r
+cuisine: [ british ]\r
+meal_type: [dinner]\r
+---\r
+#   Notes\r
+Some notes.   \r
+\r
+\r
+# Ingredients\r
+\r
+* 1 [onion], diced\r
++   2 cloves [garlic]\r
+\r
+\r
+\r
+- 400 g [chopped tomatoes]\r
+\r
+# Instructions\r
+1) Fry the {onion} and {garlic}.\r
+\r
+1. Add the {chopped tomatoes}.\r
+# Serving Suggestions\r
+With bread.\r
+\r
+\r
+";
+        assert_eq!(format_recipe(messy, &CanonicalData::empty()).unwrap(), FORMATTED);
+    }
+
+    #[test]
+    fn test_formatting_is_idempotent() {
+        let messy = FORMATTED.replace("- 2 cloves", "*  2 cloves").replace("2. Add", "\n7. Add");
+        let once = format_recipe(&messy, &CanonicalData::empty()).unwrap();
+        assert_eq!(format_recipe(&once, &CanonicalData::empty()).unwrap(), once);
+    }
+
+    #[test]
+    fn test_flow_items_are_quoted_when_needed() {
+        let source = FORMATTED.replace("cuisine: [british]", "cuisine:\n  - \"a, b\"\n  - british");
+        let formatted = format_recipe(&source, &CanonicalData::empty()).unwrap();
+        assert!(formatted.contains("cuisine: [\"a, b\", british]\n"));
+    }
+
+    #[test]
+    fn test_rejects_missing_frontmatter() {
+        assert!(format_recipe("# Ingredients\n\n- 1 [onion]\n", &CanonicalData::empty()).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! `--imperial`: an imperial/US-customary alternative next to every metric quantity, for the
+//! unit toggle on the roadmap.
+//!
+//! Volumes become cups, or fluid ounces and spoons below a quarter cup. Weights become cups
+//! too when the ingredient has a density in `ingredients.json`, as US recipes measure flour and
+//! sugar; otherwise ounces and pounds. Cups and fluid ounces are derived from the vocabulary's
+//! `cup`, so they agree with how cups merge. Amounts are rounded to what a kitchen measures.
+
+use crate::shopping::format_amount;
+use crate::{CanonicalData, Dimension, ParsedQuantity, Recipe};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Units that get an alternative; spoons and cups are already customary.
+const METRIC_UNITS: [&str; 4] = ["g", "kg", "ml", "l"];
+
+const GRAMS_PER_OUNCE: f64 = 28.349523125;
+const OUNCES_PER_POUND: f64 = 16.0;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Imperial {
+    pub(crate) amount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) amount_max: Option<f64>,
+    /// "oz", "lb", "fl oz", "cup", "tbsp" or "tsp"
+    pub(crate) unit: String,
+    /// Ready to show, e.g. "1 1/2 cups"
+    pub(crate) text: String,
+}
+
+/// Round to a whole number plus a quarter, third, half, two thirds or three quarters; whole
+/// numbers only from 10.
+fn kitchen_round(n: f64) -> f64 {
+    const STEPS: [f64; 7] = [0.0, 0.25, 1.0 / 3.0, 0.5, 2.0 / 3.0, 0.75, 1.0];
+    if n >= 10.0 {
+        return n.round();
+    }
+    let whole = n.floor();
+    let step = STEPS.into_iter().min_by(|a, b| (n - whole - a).abs().total_cmp(&(n - whole - b).abs())).unwrap_or(0.0);
+    ((whole + step) * 1000.0).round() / 1000.0
+}
+
+/// Millilitres in a volume unit of the vocabulary.
+fn millilitres(unit: &str, canonical: &CanonicalData) -> Option<f64> {
+    canonical.unit_dimension(unit).filter(|(dimension, _)| *dimension == Dimension::Volume).map(|(_, ml)| ml)
+}
+
+/// The unit to give `ml` in, with its size in millilitres: cups from a quarter cup, then fluid
+/// ounces (liquids only) from one, then tablespoons and teaspoons.
+fn volume_unit(ml: f64, liquid: bool, canonical: &CanonicalData) -> Option<(&'static str, f64)> {
+    let cup = millilitres("cup", canonical)?;
+    let ladder = [
+        ("cup", cup, 0.25),
+        ("fl oz", cup / 8.0, if liquid { 1.0 } else { f64::INFINITY }),
+        ("tbsp", millilitres("tbsp", canonical)?, 1.0),
+        ("tsp", millilitres("tsp", canonical)?, 0.0),
+    ];
+    ladder.into_iter().find(|(_, size, min)| ml / size >= *min).map(|(unit, size, _)| (unit, size))
+}
+
+/// The US-customary alternative of `q`, when it's in metric units. `density` (g/ml) lets a
+/// weight become a volume.
+pub(crate) fn alternative(q: &ParsedQuantity, density: Option<f64>, canonical: &CanonicalData) -> Option<Imperial> {
+    let unit = q.unit.as_deref().filter(|unit| METRIC_UNITS.contains(unit))?;
+    let (dimension, factor) = canonical.unit_dimension(unit)?;
+    // Grams or millilitres, as the quantity is, in one of the target unit
+    let base = q.amount * factor;
+    let (name, size) = match (dimension, density) {
+        (Dimension::Mass, Some(density)) => volume_unit(base / density, false, canonical).map(|(name, ml)| (name, ml * density))?,
+        (Dimension::Mass, None) if base >= GRAMS_PER_OUNCE * OUNCES_PER_POUND => ("lb", GRAMS_PER_OUNCE * OUNCES_PER_POUND),
+        (Dimension::Mass, None) => ("oz", GRAMS_PER_OUNCE),
+        (Dimension::Volume, _) => volume_unit(base, true, canonical)?,
+        (Dimension::Count, _) => return None,
+    };
+
+    let amount = kitchen_round(base / size);
+    if amount == 0.0 {
+        // Less than a kitchen can measure in this unit
+        return None;
+    }
+    let amount_max = q.amount_max.map(|max| kitchen_round(max * factor / size));
+    // "1/2 cup", "1 cup", "1 1/2 cups"
+    let shown = if name == "cup" && amount_max.unwrap_or(amount) > 1.0 { "cups" } else { name };
+    let max = amount_max.map(|max| format!("-{}", format_amount(max))).unwrap_or_default();
+    Some(Imperial {
+        amount,
+        amount_max,
+        unit: name.to_string(),
+        text: format!("{}{} {}", format_amount(amount), max, shown),
+    })
+}
+
+/// Set the imperial alternative of every quantity in `recipes`. Returns the ingredients whose
+/// weights had no density to become cups, which get ounces or pounds instead.
+pub(crate) fn annotate(recipes: &mut [Recipe], canonical: &CanonicalData) -> Vec<String> {
+    let mut skipped = BTreeSet::new();
+    for ingredient in recipes.iter_mut().flat_map(|r| r.ingredients.values_mut()).flatten() {
+        let Some(q) = &mut ingredient.quantity else { continue };
+        let density = ingredient.canonical.as_ref().and_then(|name| canonical.ingredient_densities.get(name)).copied();
+        if density.is_none() && q.dimension == Some(Dimension::Mass) && q.unit.as_deref().is_some_and(|u| METRIC_UNITS.contains(&u)) {
+            skipped.insert(ingredient.canonical.clone().unwrap_or_else(|| q.item.to_lowercase()));
+        }
+        q.imperial = alternative(q, density, canonical);
+    }
+    skipped.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{vocabulary, TestRecipe};
+    use crate::VALID_CATEGORIES;
+
+    fn canonical() -> CanonicalData {
+        let mut data = vocabulary(&[("flour", "Pantry"), ("tofu", "Fridge"), ("oat milk", "Fridge"), ("salt", "Spices"), ("garlic", "Fresh")], &[]);
+        data.ingredient_densities.insert("flour".to_string(), 125.0 / 240.0);
+        data
+    }
+
+    fn texts(ingredients: &str) -> (Vec<Option<String>>, Vec<String>) {
+        let canonical = canonical();
+        let mut recipes = vec![TestRecipe::new("bake").ingredients(ingredients).parse(&canonical)];
+        let skipped = annotate(&mut recipes, &canonical);
+        let texts = VALID_CATEGORIES.iter().flat_map(|c| recipes[0].ingredients.get(*c)).flatten()
+            .map(|i| i.quantity.as_ref().and_then(|q| q.imperial.as_ref()).map(|imperial| imperial.text.clone()))
+            .collect();
+        (texts, skipped)
+    }
+
+    #[test]
+    fn test_kitchen_round() {
+        assert_eq!(kitchen_round(1.04), 1.0);
+        assert_eq!(kitchen_round(0.3), 0.333);
+        assert_eq!(kitchen_round(2.73), 2.75);
+        assert_eq!(kitchen_round(14.1), 14.0);
+        assert_eq!(kitchen_round(0.1), 0.0);
+    }
+
+    #[test]
+    fn test_imperial_alternatives() {
+        let (texts, skipped) = texts(
+            "- 250 g [flour]\n- 30 g [flour]\n- 60 g [flour]\n- 400 g [tofu]\n- 1 kg [tofu]\n- 500 ml [oat milk]\n- 45 ml [oat milk]\n- 1 tsp [salt]\n- 2 cloves [garlic]\n- 1 g [salt]",
+        );
+        // Sections in order: Fresh, Fridge, Pantry, Spices
+        assert_eq!(texts, vec![
+            None,
+            Some("14 oz".into()), Some("2 1/4 lb".into()), Some("2 cups".into()), Some("1 1/2 fl oz".into()),
+            Some("2 cups".into()), Some("3 3/4 tbsp".into()), Some("1/2 cup".into()),
+            None, None,
+        ]);
+        assert_eq!(skipped, vec!["salt", "tofu"]);
+    }
+}
@@ -0,0 +1,703 @@
+//! `import`: turn a recipe published on the web into our markdown format. Reads the
+//! schema.org `Recipe` from the JSON-LD in a locally saved page, tags `[canonical]`
+//! ingredients and links them from the steps. Anything that had to be guessed is listed in a
+//! `# Review` section, which fails `--lint` until someone has checked it and removed it.
+
+use crate::new::{self, Frontmatter};
+use crate::{fix, fmt, parse_ingredient_quantity, CanonicalData, REVIEW_SECTION, VALID_CUISINES, VALID_DIETS, VALID_MEAL_TYPES};
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Source {
+    /// schema.org Recipe in a `<script type="application/ld+json">` block
+    Jsonld,
+}
+
+/// A converted recipe, before it is written out. Ingredient lines and steps are already in
+/// our markdown syntax.
+#[derive(Debug)]
+pub(crate) struct Imported {
+    pub(crate) frontmatter: Frontmatter,
+    pub(crate) notes: Option<String>,
+    pub(crate) ingredients: Vec<String>,
+    pub(crate) steps: Vec<String>,
+    pub(crate) serving_suggestions: Option<String>,
+    /// Fields that were guessed or couldn't be converted, for the `# Review` section.
+    pub(crate) review: Vec<String>,
+}
+
+pub(crate) fn run(from: Source, file: &Path, id: Option<String>, input: &Path, canonical: &CanonicalData) -> Result<()> {
+    let Source::Jsonld = from;
+    let html = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read file: {:?}", file))?;
+    let recipe = find_jsonld_recipe(&html)
+        .with_context(|| format!("No schema.org Recipe found in {}", file.display()))?;
+
+    let mut imported = convert(&recipe, canonical, new::today())?;
+    if let Some(id) = id {
+        imported.frontmatter.id = id;
+    }
+    require_id(&imported, "pass one with --id")?;
+    let path = new::target_path(input, &imported.frontmatter.id)?;
+    save(&imported, &path, canonical)
+}
+
+/// Fail when no id could be derived from the recipe's name, e.g. one without Latin letters.
+/// `hint` says how to give one.
+pub(crate) fn require_id(imported: &Imported, hint: &str) -> Result<()> {
+    if imported.frontmatter.id.is_empty() {
+        bail!("Couldn't derive an id from '{}'; {}", imported.frontmatter.name, hint);
+    }
+    Ok(())
+}
+
+/// Write an imported recipe to `path` and list what needs reviewing.
+pub(crate) fn save(imported: &Imported, path: &Path, canonical: &CanonicalData) -> Result<()> {
+    if path.exists() {
+        bail!("{} already exists", path.display());
+    }
+    // Unit spacing and unicode fractions are fixed the same way `--fix` would
+    let (source, _) = fix::fix_source(&render(imported), canonical);
+    let source = fmt::format_recipe(&source, canonical).unwrap_or(source);
+    fs::write(path, source)
+        .with_context(|| format!("Failed to write recipe: {:?}", path))?;
+
+    println!("📥 Imported '{}' to {}", imported.frontmatter.name, path.display());
+    if !imported.review.is_empty() {
+        println!("🔎 {} thing(s) to review (listed under '# {}' in the file):", imported.review.len(), REVIEW_SECTION);
+        for item in &imported.review {
+            println!("   - {}", item);
+        }
+        println!("   Check the recipe, delete the '# {}' section, then run `npm run lint-recipes`.", REVIEW_SECTION);
+    }
+    Ok(())
+}
+
+/// The first schema.org Recipe in the page's JSON-LD blocks.
+fn find_jsonld_recipe(html: &str) -> Option<Value> {
+    static SCRIPT: OnceLock<regex::Regex> = OnceLock::new();
+    let script = SCRIPT.get_or_init(|| {
+        regex::Regex::new(r#"(?is)<script[^>]*type\s*=\s*["']?application/ld\+json["']?[^>]*>(.*?)</script>"#).unwrap()
+    });
+    script.captures_iter(html).find_map(|cap| {
+        let json = cap[1].trim();
+        // Some sites leave raw newlines inside JSON strings
+        let value = serde_json::from_str::<Value>(json)
+            .or_else(|_| serde_json::from_str::<Value>(&json.replace(['\n', '\r', '\t'], " ")))
+            .ok()?;
+        find_recipe(&value)
+    })
+}
+
+fn find_recipe(value: &Value) -> Option<Value> {
+    match value {
+        Value::Array(items) => items.iter().find_map(find_recipe),
+        Value::Object(object) => {
+            let is_recipe = match object.get("@type") {
+                Some(Value::String(t)) => t == "Recipe",
+                Some(Value::Array(types)) => types.iter().any(|t| t == "Recipe"),
+                _ => false,
+            };
+            if is_recipe {
+                return Some(value.clone());
+            }
+            ["@graph", "mainEntity"].iter().find_map(|key| object.get(*key).and_then(find_recipe))
+        }
+        _ => None,
+    }
+}
+
+fn convert(recipe: &Value, canonical: &CanonicalData, date: String) -> Result<Imported> {
+    let mut review = Vec::new();
+
+    let Some(name) = text(&recipe["name"]) else {
+        bail!("The recipe has no name");
+    };
+
+    let description = match text(&recipe["description"]) {
+        Some(d) if d.len() > 500 => {
+            review.push("description: shortened to fit the 500 character limit".to_string());
+            shorten(&d, 500)
+        }
+        Some(d) if d.len() >= 10 => d,
+        _ => {
+            review.push("description: missing from the page; write one".to_string());
+            name.clone()
+        }
+    };
+
+    let servings = match text(&recipe["recipeYield"]).as_deref().and_then(first_number) {
+        Some(n) if (1..=100).contains(&n) => n,
+        _ => {
+            review.push("servings: no usable recipeYield on the page; guessed 4".to_string());
+            4
+        }
+    };
+
+    let time = match text(&recipe["totalTime"]).as_deref().and_then(iso_minutes) {
+        Some(minutes) => minutes,
+        None => {
+            let parts: Vec<u32> = ["prepTime", "cookTime"].iter()
+                .filter_map(|key| text(&recipe[*key]).as_deref().and_then(iso_minutes))
+                .collect();
+            if parts.is_empty() {
+                review.push("time: no totalTime, prepTime or cookTime on the page; guessed 30 minutes".to_string());
+                30
+            } else {
+                let total = parts.iter().sum();
+                review.push(format!("time: no totalTime on the page; used prepTime + cookTime ({} minutes)", total));
+                total
+            }
+        }
+    };
+
+    // schema.org has no difficulty
+    let difficulty = match time {
+        0..=30 => "easy",
+        31..=90 => "medium",
+        _ => "hard",
+    };
+    review.push(format!("difficulty: not on the page; guessed '{}' from the time", difficulty));
+
+    let diets: Vec<String> = terms(&recipe["suitableForDiet"]).iter().filter_map(|d| {
+        let d = d.to_lowercase();
+        let d = d.rsplit('/').next().unwrap_or_default();
+        match d {
+            "vegandiet" => Some("vegan"),
+            "vegetariandiet" => Some("vegetarian"),
+            "glutenfreediet" => Some("gluten-free"),
+            _ => None,
+        }
+    }).map(str::to_string).collect();
+    let diet = if diets.is_empty() {
+        let keywords = format!("{} {}", name, terms(&recipe["keywords"]).join(" ")).to_lowercase().replace("gluten free", "gluten-free");
+        let guessed: Vec<String> = VALID_DIETS.iter().filter(|d| keywords.contains(*d)).map(|d| d.to_string()).collect();
+        if guessed.is_empty() {
+            review.push(format!("diet: not on the page; choose from {}", VALID_DIETS.join(", ")));
+        } else {
+            review.push("diet: taken from the page's keywords; check it".to_string());
+        }
+        guessed
+    } else {
+        diets
+    };
+
+    let cuisine = mapped(&recipe["recipeCuisine"], cuisine_for, "cuisine", VALID_CUISINES, &mut review);
+    let meal_type = mapped(&recipe["recipeCategory"], meal_type_for, "meal_type", VALID_MEAL_TYPES, &mut review);
+
+    let notes = text(&recipe["url"]).or_else(|| text(&recipe["mainEntityOfPage"]))
+        .filter(|url| url.starts_with("http"))
+        .map(|url| format!("Adapted from <{}>.", url));
+
+    let mut ingredients = Vec::new();
+    let mut tagged = Vec::new();
+    for line in strings(&recipe["recipeIngredient"]).iter().map(|l| clean(l)).filter(|l| !l.is_empty()) {
+        let (tagged_line, tag) = tag_ingredient(&line, canonical);
+        match tag {
+            Some(tag) => {
+                // "a handful of basil" names basil; "a handful of Thai basil" doesn't
+                let (name, form) = (ingredient_name(&line, canonical), tag.0.to_lowercase());
+                if name != form && !name.ends_with(&format!(" of {}", form)) {
+                    review.push(format!("ingredient '{}': [{}] is only part of '{}'; check the tag", tagged_line, tag.0, name));
+                }
+                tagged.push(tag);
+            }
+            None => review.push(format!("ingredient '{}': no match in ingredients.json; add a [canonical] tag", tagged_line)),
+        }
+        ingredients.push(tagged_line);
+    }
+    if ingredients.is_empty() {
+        review.push("ingredients: none on the page".to_string());
+    }
+
+    let mut steps = Vec::new();
+    collect_steps(&recipe["recipeInstructions"], &mut steps);
+    if steps.is_empty() {
+        review.push("instructions: none on the page".to_string());
+    }
+    let steps = link_steps(steps, &tagged, canonical);
+
+    let frontmatter = Frontmatter {
+        id: new::slugify(&name),
+        name,
+        description,
+        servings,
+        time,
+        difficulty: difficulty.to_string(),
+        diet,
+        cuisine,
+        meal_type,
+        date,
+        tested: None,
+    };
+    Ok(Imported { frontmatter, notes, ingredients, steps, serving_suggestions: None, review })
+}
+
+pub(crate) fn render(imported: &Imported) -> String {
+    let mut out = new::render_frontmatter(&imported.frontmatter);
+    if !imported.review.is_empty() {
+        out.push_str(&format!("\n# {}\n\nImported automatically; check these, then delete this section:\n\n", REVIEW_SECTION));
+        for item in &imported.review {
+            out.push_str(&format!("- {}\n", item));
+        }
+    }
+    out.push_str("\n# Notes\n\n");
+    if let Some(notes) = &imported.notes {
+        out.push_str(notes);
+        out.push_str("\n\n");
+    }
+    out.push_str("# Ingredients\n\n");
+    for line in &imported.ingredients {
+        out.push_str(&format!("- {}\n", line));
+    }
+    out.push_str("\n# Instructions\n\n");
+    for (i, step) in imported.steps.iter().enumerate() {
+        out.push_str(&format!("{}. {}\n", i + 1, step));
+    }
+    out.push_str("\n# Serving Suggestions\n");
+    if let Some(suggestions) = &imported.serving_suggestions {
+        out.push('\n');
+        out.push_str(suggestions);
+        out.push('\n');
+    }
+    out
+}
+
+/// Plain text of a JSON-LD value: strings are cleaned of markup, lists give their first
+/// entry, and objects their `text`, `name` or `@id`.
+fn text(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(s) => clean(s),
+        Value::Number(n) => n.to_string(),
+        Value::Array(items) => return items.iter().find_map(text),
+        Value::Object(object) => return ["text", "name", "@id"].iter().find_map(|key| object.get(*key).and_then(text)),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// Every string in a value that may be a single string or a list.
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(items) => items.iter().flat_map(strings).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Like `strings`, also splitting comma-separated lists such as `"keywords": "vegan, quick"`.
+fn terms(value: &Value) -> Vec<String> {
+    strings(value).iter().flat_map(|s| s.split(',')).map(clean).filter(|s| !s.is_empty()).collect()
+}
+
+/// Allowed values for a field mapped from the imported recipe (a web page or Cooklang metadata),
+/// noting any it didn't provide.
+pub(crate) fn mapped(
+    value: &Value,
+    map: fn(&str) -> Option<&'static str>,
+    field: &str,
+    allowed: &[&str],
+    review: &mut Vec<String>,
+) -> Vec<String> {
+    let raw = terms(value);
+    let mut values: Vec<String> = Vec::new();
+    for v in raw.iter().filter_map(|r| map(&r.to_lowercase())) {
+        if !values.iter().any(|existing| existing == v) {
+            values.push(v.to_string());
+        }
+    }
+    if values.is_empty() {
+        let found = if raw.is_empty() { "not given".to_string() } else { format!("given as '{}'", raw.join(", ")) };
+        review.push(format!("{}: {}; choose from {}", field, found, allowed.join(", ")));
+    }
+    values
+}
+
+pub(crate) fn cuisine_for(value: &str) -> Option<&'static str> {
+    let value = value.trim().replace(' ', "-");
+    if let Some(valid) = VALID_CUISINES.iter().find(|c| **c == value) {
+        return Some(valid);
+    }
+    Some(match value.as_str() {
+        "thai" | "chinese" | "japanese" | "korean" | "vietnamese" | "indonesian" | "malaysian" | "filipino" => "asian",
+        "lebanese" | "turkish" | "persian" | "iranian" | "israeli" | "syrian" | "middle-east" => "middle-eastern",
+        "greek" | "spanish" | "cypriot" => "mediterranean",
+        "english" | "scottish" | "welsh" | "irish" | "uk" => "british",
+        "us" | "usa" | "southern" | "tex-mex" => "american",
+        _ => return None,
+    })
+}
+
+pub(crate) fn meal_type_for(value: &str) -> Option<&'static str> {
+    let value = value.trim();
+    let has = |words: &[&str]| words.iter().any(|w| value.contains(w));
+    Some(if has(&["breakfast"]) {
+        "breakfast"
+    } else if has(&["brunch"]) {
+        "brunch"
+    } else if has(&["lunch", "salad", "soup", "sandwich"]) {
+        "lunch"
+    } else if has(&["dinner", "main", "entree", "entrée", "supper"]) {
+        "dinner"
+    } else if has(&["dessert", "pudding"]) {
+        "dessert"
+    } else if has(&["baking", "bread", "cake", "cookie", "biscuit"]) {
+        "baking"
+    } else {
+        return None;
+    })
+}
+
+/// Flatten recipeInstructions (a string, a list of strings, HowToSteps or HowToSections)
+/// into step texts.
+fn collect_steps(value: &Value, steps: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            let s = s.replace("<br>", "\n").replace("<br/>", "\n").replace("<br />", "\n").replace("</p>", "\n");
+            steps.extend(s.lines().map(|l| strip_step_number(&clean(l)).to_string()).filter(|l| !l.is_empty()));
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_steps(item, steps)),
+        Value::Object(object) => {
+            if let Some(items) = object.get("itemListElement") {
+                collect_steps(items, steps);
+            } else if let Some(text) = object.get("text").or_else(|| object.get("name")) {
+                collect_steps(text, steps);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Drop a leading "1.", "2)" or "Step 3:" the page put in the text itself.
+fn strip_step_number(step: &str) -> &str {
+    static NUMBER: OnceLock<regex::Regex> = OnceLock::new();
+    let number = NUMBER.get_or_init(|| regex::Regex::new(r"(?i)^(?:step\s*)?\d+\s*[.):]\s*").unwrap());
+    match number.find(step) {
+        Some(m) => &step[m.end()..],
+        None => step,
+    }
+}
+
+/// Tag the longest vocabulary ingredient named in `line` as `[canonical]`. Returns the tagged
+/// line and the (surface form, canonical) that was tagged.
+fn tag_ingredient(line: &str, canonical: &CanonicalData) -> (String, Option<(String, String)>) {
+    // Look in the item part only, so units like "cloves" aren't taken for ingredients
+    let item = parse_ingredient_quantity(line, &canonical.units).map(|q| q.item);
+    let search_from = item.as_deref().and_then(|item| line.rfind(item)).unwrap_or(0);
+
+    let lower = line.to_ascii_lowercase();
+    let best = canonical.ingredients.iter()
+        .filter_map(|(form, resolved)| find_word(&lower, form, search_from).map(|start| (start, form, resolved)))
+        .max_by(|a, b| a.1.len().cmp(&b.1.len()).then(b.0.cmp(&a.0)));
+    match best {
+        Some((start, form, resolved)) => {
+            let end = start + form.len();
+            let tagged = format!("{}[{}]{}", &line[..start], &line[start..end], &line[end..]);
+            (tagged, Some((line[start..end].to_string(), resolved.clone())))
+        }
+        None => (line.to_string(), None),
+    }
+}
+
+/// What `line` names, lowercased: the item after the quantity, up to a comma or parenthesis.
+fn ingredient_name(line: &str, canonical: &CanonicalData) -> String {
+    let item = parse_ingredient_quantity(line, &canonical.units).map_or_else(|| line.to_string(), |q| q.item);
+    item.split([',', '(']).next().unwrap_or_default().trim().to_lowercase()
+}
+
+/// Byte offset of the first whole-word occurrence of `word` in `haystack` at or after `from`.
+fn find_word(haystack: &str, word: &str, from: usize) -> Option<usize> {
+    let mut start = from;
+    while let Some(i) = haystack.get(start..)?.find(word) {
+        let at = start + i;
+        let end = at + word.len();
+        let boundary_before = haystack[..at].chars().next_back().is_none_or(|c| !c.is_alphanumeric());
+        let boundary_after = haystack[end..].chars().next().is_none_or(|c| !c.is_alphanumeric());
+        if boundary_before && boundary_after {
+            return Some(at);
+        }
+        start = at + word.len().max(1);
+    }
+    None
+}
+
+/// Wrap the first mention of each tagged ingredient in every step as a `{reference}`.
+/// Ingredients listed more than once are left alone, as a reference to them would be ambiguous.
+fn link_steps(steps: Vec<String>, tagged: &[(String, String)], canonical: &CanonicalData) -> Vec<String> {
+    let mut forms: Vec<(String, &str)> = Vec::new();
+    for (surface, resolved) in tagged {
+        if tagged.iter().filter(|(_, r)| r == resolved).count() > 1 {
+            continue;
+        }
+        forms.push((surface.to_ascii_lowercase(), resolved));
+        for (form, r) in &canonical.ingredients {
+            if r == resolved && !forms.iter().any(|(f, _)| f == form) {
+                forms.push((form.clone(), resolved));
+            }
+        }
+    }
+    // Longest first, so "olive oil" is linked before "oil" could be
+    forms.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(&b.0)));
+
+    steps.into_iter().map(|mut step| {
+        let mut linked: Vec<&str> = Vec::new();
+        for (form, resolved) in &forms {
+            if linked.contains(resolved) {
+                continue;
+            }
+            let lower = step.to_ascii_lowercase();
+            let mut from = 0;
+            while let Some(start) = find_word(&lower, form, from) {
+                let end = start + form.len();
+                if inside_braces(&step, start) {
+                    from = end;
+                    continue;
+                }
+                step = format!("{}{{{}}}{}", &step[..start], &step[start..end], &step[end..]);
+                linked.push(resolved);
+                break;
+            }
+        }
+        step
+    }).collect()
+}
+
+fn inside_braces(text: &str, at: usize) -> bool {
+    let before = &text[..at];
+    before.rfind('{').is_some_and(|open| before.rfind('}').is_none_or(|close| close < open))
+}
+
+/// Minutes in an ISO 8601 duration such as `PT1H30M` or `P0DT45M`, rounded up.
+fn iso_minutes(duration: &str) -> Option<u32> {
+    static ISO: OnceLock<regex::Regex> = OnceLock::new();
+    let iso = ISO.get_or_init(|| {
+        regex::Regex::new(r"(?i)^P(?:(\d+(?:\.\d+)?)D)?(?:T(?:(\d+(?:\.\d+)?)H)?(?:(\d+(?:\.\d+)?)M)?(?:(\d+(?:\.\d+)?)S)?)?$").unwrap()
+    });
+    let cap = iso.captures(duration.trim())?;
+    let part = |i: usize| cap.get(i).and_then(|m| m.as_str().parse::<f64>().ok()).unwrap_or(0.0);
+    let minutes = part(1) * 1440.0 + part(2) * 60.0 + part(3) + part(4) / 60.0;
+    (minutes > 0.0).then(|| minutes.ceil() as u32)
+}
+
+pub(crate) fn first_number(text: &str) -> Option<u32> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let digits: String = text[start..].chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// Cut `text` to at most `max` bytes, at the end of a sentence if there is one, else a word.
+fn shorten(text: &str, max: usize) -> String {
+    let mut end = max.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = &text[..end];
+    match head.rfind(". ") {
+        Some(i) if i > max / 2 => head[..=i].to_string(),
+        _ => {
+            // Room for the ellipsis, which is 3 bytes
+            let mut cut = head.rfind(' ').unwrap_or(end).min(end.saturating_sub(3));
+            while !head.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            format!("{}…", head[..cut].trim_end())
+        }
+    }
+}
+
+/// Page text as plain text: tags removed, entities decoded, whitespace collapsed.
+fn clean(html: &str) -> String {
+    static TAG: OnceLock<regex::Regex> = OnceLock::new();
+    let tag = TAG.get_or_init(|| regex::Regex::new(r"<[^>]*>").unwrap());
+    let text = decode_entities(&tag.replace_all(html, " "));
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&semi| semi <= 10).and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                "frac12" => '½',
+                "frac14" => '¼',
+                "frac34" => '¾',
+                entity => {
+                    let code = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                        .map(|hex| u32::from_str_radix(hex, 16))
+                        .or_else(|| entity.strip_prefix('#').map(str::parse))?
+                        .ok()?;
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Diagnostics;
+    use crate::{fixtures, parse_recipe};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+<script type="application/ld+json">{"@context": "https://schema.org", "@type": "WebSite", "name": "Example"}</script>
+<script type='application/ld+json'>
+{"@context": "https://schema.org", "@graph": [
+  {"@type": "WebPage", "@id": "https://example.com/thai-green-curry"},
+  {"@type": ["Recipe", "NewsArticle"],
+   "name": "Thai Green Curry",
+   "description": "A <b>fragrant</b> curry with vegetables &amp; tofu",
+   "url": "https://example.com/thai-green-curry",
+   "recipeYield": ["4", "4 servings"],
+   "prepTime": "PT15M",
+   "cookTime": "PT25M",
+   "suitableForDiet": "https://schema.org/VeganDiet",
+   "recipeCuisine": "Thai",
+   "recipeCategory": "Main Course",
+   "recipeIngredient": ["2 tbsp vegetable oil", "3 cloves garlic, minced", "400ml coconut milk", "1 handful of Thai basil", "½ tsp salt", "1 block of tofu", "2 tbsp chilli bean paste"],
+   "recipeInstructions": [
+     {"@type": "HowToSection", "name": "Curry", "itemListElement": [
+       {"@type": "HowToStep", "text": "1. Heat the oil and fry the garlic for 1 minute."},
+       {"@type": "HowToStep", "text": "Pour in the coconut milk and simmer for 10 minutes."}
+     ]},
+     {"@type": "HowToStep", "text": "Stir in the basil &amp; season with salt."}
+   ]}
+]}
+</script>
+</head><body></body></html>"#;
+
+    fn vocabulary() -> CanonicalData {
+        fixtures::vocabulary(
+            &[("vegetable oil", "Pantry"), ("oil", "Pantry"), ("garlic", "Fresh"), ("coconut milk", "Pantry"), ("basil", "Fresh"),
+                ("salt", "Spices"), ("clove", "Spices"), ("chilli", "Fresh")],
+            &[("cloves", "clove")],
+        )
+    }
+
+    #[test]
+    fn test_iso_durations() {
+        assert_eq!(iso_minutes("PT45M"), Some(45));
+        assert_eq!(iso_minutes("PT1H30M"), Some(90));
+        assert_eq!(iso_minutes("P0DT2H"), Some(120));
+        assert_eq!(iso_minutes("PT90S"), Some(2));
+        assert_eq!(iso_minutes("pt0.5h"), Some(30));
+        assert_eq!(iso_minutes("PT0M"), None);
+        assert_eq!(iso_minutes("45 minutes"), None);
+    }
+
+    #[test]
+    fn test_finds_recipe_in_graph() {
+        let recipe = find_jsonld_recipe(PAGE).unwrap();
+        assert_eq!(recipe["name"], "Thai Green Curry");
+        assert!(find_jsonld_recipe("<html><body>No data</body></html>").is_none());
+    }
+
+    #[test]
+    fn test_converts_recipe() {
+        let recipe = find_jsonld_recipe(PAGE).unwrap();
+        let imported = convert(&recipe, &vocabulary(), "2026-10-17".into()).unwrap();
+        let fm = &imported.frontmatter;
+        assert_eq!(fm.id, "thai-green-curry");
+        assert_eq!(fm.description, "A fragrant curry with vegetables & tofu");
+        assert_eq!((fm.servings, fm.time), (4, 40));
+        assert_eq!((fm.diet.clone(), fm.cuisine.clone(), fm.meal_type.clone()), (vec!["vegan".into()], vec!["asian".into()], vec!["dinner".into()]));
+        assert_eq!(imported.notes.as_deref(), Some("Adapted from <https://example.com/thai-green-curry>."));
+
+        assert_eq!(imported.ingredients, vec![
+            "2 tbsp [vegetable oil]",
+            "3 cloves [garlic], minced",
+            "400ml [coconut milk]",
+            "1 handful of Thai [basil]",
+            "½ tsp [salt]",
+            "1 block of tofu",
+            "2 tbsp [chilli] bean paste",
+        ]);
+        assert_eq!(imported.steps, vec![
+            "Heat the oil and fry the {garlic} for 1 minute.",
+            "Pour in the {coconut milk} and simmer for 10 minutes.",
+            "Stir in the {basil} & season with {salt}.",
+        ]);
+
+        assert_eq!(imported.review, vec![
+            "time: no totalTime on the page; used prepTime + cookTime (40 minutes)",
+            "difficulty: not on the page; guessed 'medium' from the time",
+            "ingredient '1 handful of Thai [basil]': [basil] is only part of 'handful of thai basil'; check the tag",
+            "ingredient '1 block of tofu': no match in ingredients.json; add a [canonical] tag",
+            "ingredient '2 tbsp [chilli] bean paste': [chilli] is only part of 'chilli bean paste'; check the tag",
+        ]);
+    }
+
+    #[test]
+    fn test_written_recipe_only_fails_lint_on_review_items() {
+        let recipe = find_jsonld_recipe(PAGE).unwrap();
+        let canonical = vocabulary();
+        let imported = convert(&recipe, &canonical, "2026-10-17".into()).unwrap();
+        let (source, _) = fix::fix_source(&render(&imported), &canonical);
+        assert!(source.contains("- 400 ml [coconut milk]\n- 1 handful of Thai [basil]\n- 1/2 tsp [salt]\n"));
+        assert!(source.contains("\n1. Heat the oil and fry the {garlic} for 1 minute.\n2. Pour in"));
+
+        let mut diagnostics = Diagnostics::default();
+        assert!(parse_recipe(&source, false, &canonical, &mut diagnostics).is_some());
+        parse_recipe(&source, true, &canonical, &mut diagnostics);
+        let mut rules: Vec<&str> = diagnostics.iter().filter(|d| d.severity == crate::diagnostics::Severity::Error).map(|d| d.rule).collect();
+        rules.sort();
+        assert_eq!(rules, vec!["missing-canonical", "review-section"]);
+    }
+
+    #[test]
+    fn test_missing_fields_are_left_for_review() {
+        let recipe: Value = serde_json::from_str(r#"{"@type": "Recipe", "name": "Mystery Stew", "recipeCuisine": "Martian",
+            "recipeInstructions": "Step 1: Chop everything.<br>Step 2: Stew it."}"#).unwrap();
+        let imported = convert(&recipe, &vocabulary(), "2026-10-17".into()).unwrap();
+        assert!(imported.frontmatter.cuisine.is_empty());
+        assert_eq!(imported.steps, vec!["Chop everything.", "Stew it."]);
+        let review = imported.review.join("\n");
+        assert!(review.contains("cuisine: given as 'Martian'; choose from indian, middle-eastern"));
+        assert!(review.contains("meal_type: not given"));
+        assert!(review.contains("diet: not on the page"));
+        assert!(review.contains("servings: no usable recipeYield on the page; guessed 4"));
+        assert!(review.contains("ingredients: none on the page"));
+
+        let recipe: Value = serde_json::from_str(r#"{"@type": "Recipe", "name": "麻婆豆腐"}"#).unwrap();
+        let imported = convert(&recipe, &vocabulary(), "2026-10-17".into()).unwrap();
+        let err = require_id(&imported, "pass one with --id").unwrap_err();
+        assert_eq!(err.to_string(), "Couldn't derive an id from '麻婆豆腐'; pass one with --id");
+    }
+
+    #[test]
+    fn test_decodes_page_text() {
+        assert_eq!(clean("<p>Salt &amp; pepper&nbsp;to&#160;taste &#x2013; &frac12; tsp</p>"), "Salt & pepper to taste – ½ tsp");
+        assert_eq!(clean("Fish & chips &unknown; a&b"), "Fish & chips &unknown; a&b");
+        assert_eq!(shorten("One two three four", 12), "One two…");
+        // The last space is right before the limit, after a 2-byte 'é'
+        let accented = format!("{}é {}", "a".repeat(496), "b".repeat(100));
+        assert_eq!(shorten(&accented, 500), format!("{}…", "a".repeat(496)));
+    }
+}
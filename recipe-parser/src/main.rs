@@ -1,13 +1,50 @@
 use anyhow::{bail, Context, Result};
-use clap::Parser as ClapParser;
+use clap::{Parser as ClapParser, Subcommand};
 use pulldown_cmark::{Event, Parser as MarkdownParser, Tag, TagEnd};
 use serde::{Deserialize, Serialize, Serializer};
-use sha2::{Sha256, Digest};
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
 
+/// Set when a machine-readable `--format` owns stdout.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Progress output on stdout, silenced while `QUIET` is set.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::QUIET.load(::std::sync::atomic::Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod cache;
+mod cooklang;
+mod delta;
+mod diagnostics;
+mod fix;
+#[cfg(test)]
+mod fixtures;
+mod fmt;
+mod imperial;
+mod import;
+mod new;
+mod nutrition;
+mod plan;
+mod schema;
+mod search;
+mod serve;
+mod shopping;
+mod similar;
+mod watch;
+
+use cache::{sha256_hex, BuildCache};
+use diagnostics::{Diagnostics, FileReport, Format};
+
 #[derive(ClapParser)]
 #[command(name = "recipe-parser")]
 #[command(about = "Parse markdown recipes into JSON")]
@@ -23,6 +60,143 @@ struct Cli {
     /// Enable strict linting mode
     #[arg(short, long)]
     lint: bool,
+
+    /// Diagnostic output format; non-text formats write to stdout and silence progress output
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Rewrite recipe files in place to resolve autofixable lint errors
+    #[arg(long)]
+    fix: bool,
+
+    /// With --fix, print a unified diff of the fixes instead of writing them
+    #[arg(long, requires = "fix")]
+    dry_run: bool,
+
+    /// Build cache location; unchanged recipes are reused from it instead of re-parsed
+    #[arg(long, default_value = cache::DEFAULT_CACHE_PATH)]
+    cache: PathBuf,
+
+    /// Parse every recipe from scratch, ignoring and not updating the build cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Write every recipe into the output file, or one file per recipe plus a slim index
+    #[arg(long, value_enum, default_value_t = Layout::Single)]
+    layout: Layout,
+
+    /// Add an imperial/US-customary alternative (oz, lb, fl oz, cups) to every metric quantity
+    #[arg(long)]
+    imperial: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Rewrite recipe markdown into the house layout
+    Fmt {
+        /// Report unformatted recipes and exit non-zero instead of rewriting them
+        #[arg(long)]
+        check: bool,
+
+        /// Recipe files to format (defaults to every recipe in the input directory)
+        files: Vec<PathBuf>,
+    },
+    /// Scaffold a new recipe file with valid frontmatter
+    New(new::NewArgs),
+    /// Convert a recipe from another format into a new recipe file
+    Import {
+        /// Format of the file to import
+        #[arg(long, value_enum)]
+        from: import::Source,
+
+        /// File to import, e.g. a saved web page
+        file: PathBuf,
+
+        /// Use this id instead of the one derived from the recipe name
+        #[arg(long)]
+        id: Option<String>,
+    },
+    /// Convert a recipe to or from another recipe markup
+    Convert {
+        /// Export the recipe to this format
+        #[arg(long, value_enum, conflicts_with = "from", required_unless_present = "from")]
+        to: Option<RecipeFormat>,
+
+        /// Import a recipe written in this format
+        #[arg(long, value_enum)]
+        from: Option<RecipeFormat>,
+
+        /// Recipe file to convert
+        file: PathBuf,
+
+        /// Where to write the result (exports default to stdout, imports to the input directory)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of the recipes.json output
+    Schema,
+    /// Pick recipes for the coming days that share ingredients, with a merged shopping list
+    Plan(plan::PlanArgs),
+    /// Print one shopping list for several recipes, scaled and merged by ingredient
+    Shop {
+        /// Recipes as `id` or `id:servings`, e.g. dal-tadka:6 chilli-sin-carne:4
+        #[arg(required = true)]
+        recipes: Vec<String>,
+
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rank recipes for a query with the search index from the last build
+    Search {
+        /// Words to search for, e.g. "chickpea curry"
+        query: String,
+
+        /// Show at most this many recipes
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Report per-serving nutrition and coverage, and the ingredients missing nutrition data
+    Nutrition {
+        /// Fail if any recipe's coverage is below this percentage
+        #[arg(long, value_parser = clap::value_parser!(u32).range(0..=100))]
+        min_coverage: Option<u32>,
+    },
+    /// Check that a delta patch turns an old recipes.json into the version it names
+    VerifyDelta {
+        /// The recipes.json the delta applies to
+        old: PathBuf,
+
+        /// Delta file, e.g. docs/deltas/<old-version>-<new-version>.json
+        delta: PathBuf,
+    },
+    /// Rebuild the outputs whenever a recipe or docs/ingredients.json changes
+    Watch,
+    /// Serve docs/ on localhost, rebuilding and live-reloading open pages on change
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+}
+
+/// How `write_outputs` lays out the recipe data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Layout {
+    /// Every recipe in the output file, e.g. docs/recipes.json
+    Single,
+    /// docs/recipes/<id>.json per recipe, listed with their hashes in docs/recipes-index.json
+    Split,
+}
+
+/// Recipe markups `convert` can read and write.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum RecipeFormat {
+    /// https://cooklang.org
+    Cooklang,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -41,13 +215,28 @@ struct RecipeFrontmatter {
     tested: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
+/// What a unit measures. Mass and volume units convert to grams and millilitres through their
+/// vocabulary factor; count units ("clove", "tin") only ever match themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Dimension {
+    Mass,
+    Volume,
+    Count,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct ParsedQuantity {
     amount: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     amount_max: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     unit: Option<String>,
+    /// Singular vocabulary form of `unit` ("clove" for "cloves")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canonical_unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimension: Option<Dimension>,
     item: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     secondary_amount: Option<f64>,
@@ -57,9 +246,12 @@ struct ParsedQuantity {
     secondary_prefix: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     prefix: Option<String>,
+    /// With `--imperial`, the quantity in US-customary units, for metric quantities only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    imperial: Option<imperial::Imperial>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct Ingredient {
     id: u32,
     text: String,
@@ -71,20 +263,20 @@ struct Ingredient {
     quantity: Option<ParsedQuantity>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct StepDuration {
     seconds: u32,
     text: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct Step {
     text: String,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     durations: Vec<StepDuration>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct Recipe {
     id: String,
     name: String,
@@ -104,12 +296,37 @@ struct Recipe {
     #[serde(skip_serializing_if = "Option::is_none")]
     serving_suggestions: Option<String>,
     tested: bool,
+    /// Estimated from the vocabulary's nutrition table; absent when no ingredient has data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nutrition: Option<nutrition::Nutrition>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
 struct Manifest {
     version: String,
     recipe_count: usize,
+    schema_version: u32,
+    /// Split layout only: hash of every data file, keyed by its path relative to the manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<std::collections::BTreeMap<String, String>>,
+    /// Single layout only: old versions with a patch in `deltas/<old-version>-<version>.json`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    deltas: Vec<String>,
+}
+
+/// A recipe's entry in the split layout's `recipes-index.json`: enough to list and filter
+/// recipes, plus the hash of its `recipes/<id>.json` so clients only refetch what changed.
+#[derive(Serialize)]
+struct IndexEntry<'a> {
+    id: &'a str,
+    name: &'a str,
+    time: u32,
+    difficulty: &'a str,
+    diet: &'a [String],
+    cuisine: &'a [String],
+    meal_type: &'a [String],
+    tested: bool,
+    hash: String,
 }
 
 #[derive(Deserialize)]
@@ -117,12 +334,33 @@ struct CanonicalIngredientEntry {
     #[serde(default)]
     plural: Option<String>,
     section: String,
+    /// g/ml, to convert weights to cups
+    #[serde(default)]
+    density: Option<f64>,
+    /// Alternative to `density` for dry goods: grams in one cup
+    #[serde(default)]
+    grams_per_cup: Option<f64>,
+    #[serde(default)]
+    nutrition: Option<nutrition::Nutrients>,
+    /// Grams of one of the ingredient per count unit, or per `nutrition::EACH` without a unit
+    #[serde(default)]
+    grams: HashMap<String, f64>,
 }
 
 #[derive(Deserialize)]
 struct CanonicalJson {
     ingredients: HashMap<String, CanonicalIngredientEntry>,
-    units: HashMap<String, Option<String>>,
+    units: HashMap<String, CanonicalUnitEntry>,
+}
+
+#[derive(Deserialize)]
+struct CanonicalUnitEntry {
+    #[serde(default)]
+    plural: Option<String>,
+    dimension: Dimension,
+    /// Grams or millilitres in one of the unit; mass and volume units only
+    #[serde(default)]
+    factor: Option<f64>,
 }
 
 /// Canonical vocabulary loaded from docs/ingredients.json.
@@ -131,8 +369,20 @@ struct CanonicalData {
     ingredients: HashMap<String, String>,
     /// Maps singular canonical (lowercase) → section name (e.g. "Fresh", "Pantry").
     ingredient_sections: HashMap<String, String>,
+    /// Maps singular canonical (lowercase) → plural, for ingredients that have one.
+    ingredient_plurals: HashMap<String, String>,
     /// All known unit forms (both singular and plural), sorted by length descending.
     units: Vec<String>,
+    /// Maps singular unit → plural, for units that have one (e.g. "clove" → "cloves").
+    unit_plurals: HashMap<String, String>,
+    /// Maps singular unit → its dimension and, for mass and volume, grams or millilitres per unit.
+    unit_dimensions: HashMap<String, (Dimension, f64)>,
+    /// Maps singular canonical (lowercase) → density in g/ml, for ingredients that have one.
+    ingredient_densities: HashMap<String, f64>,
+    /// Maps singular canonical (lowercase) → nutrients per 100 g, for ingredients that have them.
+    ingredient_nutrition: HashMap<String, nutrition::Nutrients>,
+    /// Maps singular canonical (lowercase) → count unit (or "each") → grams in one.
+    ingredient_grams: HashMap<String, HashMap<String, f64>>,
 }
 
 impl CanonicalData {
@@ -144,27 +394,85 @@ impl CanonicalData {
 
         let mut ingredients = HashMap::new();
         let mut ingredient_sections = HashMap::new();
+        let mut ingredient_plurals = HashMap::new();
         for (key, entry) in &json.ingredients {
             let canonical = key.to_lowercase();
             ingredients.insert(canonical.clone(), canonical.clone());
             ingredient_sections.insert(canonical.clone(), entry.section.clone());
             if let Some(plural) = &entry.plural {
-                ingredients.insert(plural.to_lowercase(), canonical);
+                ingredients.insert(plural.to_lowercase(), canonical.clone());
+                ingredient_plurals.insert(canonical, plural.to_lowercase());
             }
         }
 
         let mut units: Vec<String> = Vec::new();
-        for (singular, plural_opt) in &json.units {
+        let mut unit_plurals = HashMap::new();
+        let mut unit_dimensions = HashMap::new();
+        for (singular, entry) in &json.units {
             units.push(singular.to_lowercase());
-            if let Some(plural) = plural_opt {
+            if let Some(plural) = &entry.plural {
                 units.push(plural.to_lowercase());
+                unit_plurals.insert(singular.to_lowercase(), plural.to_lowercase());
             }
+            let factor = match (entry.dimension, entry.factor) {
+                (Dimension::Count, None) => 1.0,
+                (Dimension::Count, Some(_)) => bail!("Unit '{}' counts things and can't have a factor", singular),
+                (_, Some(factor)) if factor > 0.0 => factor,
+                (_, _) => bail!("Unit '{}' needs a positive factor to grams or millilitres", singular),
+            };
+            unit_dimensions.insert(singular.to_lowercase(), (entry.dimension, factor));
         }
         // Sort by length descending so longer units are tried first (avoids prefix collisions).
         units.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
         units.dedup();
 
-        Ok(Self { ingredients, ingredient_sections, units })
+        let mut ingredient_densities = HashMap::new();
+        for (key, entry) in &json.ingredients {
+            let density = match (entry.density, entry.grams_per_cup) {
+                (None, None) => continue,
+                (Some(_), Some(_)) => bail!("Ingredient '{}' has both a density and grams_per_cup; keep one", key),
+                (Some(density), None) => density,
+                (None, Some(grams)) => match unit_dimensions.get("cup") {
+                    Some((Dimension::Volume, ml)) => grams / ml,
+                    _ => bail!("Ingredient '{}' has grams_per_cup, but 'cup' isn't a volume unit", key),
+                },
+            };
+            if density <= 0.0 {
+                bail!("Ingredient '{}' needs a positive density", key);
+            }
+            ingredient_densities.insert(key.to_lowercase(), density);
+        }
+
+        let mut ingredient_nutrition = HashMap::new();
+        let mut ingredient_grams = HashMap::new();
+        for (key, entry) in &json.ingredients {
+            if let Some(nutrients) = entry.nutrition {
+                ingredient_nutrition.insert(key.to_lowercase(), nutrients);
+            }
+            for (unit, grams) in &entry.grams {
+                if unit != nutrition::EACH && !matches!(unit_dimensions.get(unit.as_str()), Some((Dimension::Count, _))) {
+                    bail!("Ingredient '{}' has grams for '{}', which is neither a count unit nor \"{}\"", key, unit, nutrition::EACH);
+                }
+                if *grams <= 0.0 {
+                    bail!("Ingredient '{}' needs positive grams for '{}'", key, unit);
+                }
+            }
+            if !entry.grams.is_empty() {
+                ingredient_grams.insert(key.to_lowercase(), entry.grams.clone());
+            }
+        }
+
+        Ok(Self {
+            ingredients,
+            ingredient_sections,
+            ingredient_plurals,
+            units,
+            unit_plurals,
+            unit_dimensions,
+            ingredient_densities,
+            ingredient_nutrition,
+            ingredient_grams,
+        })
     }
 
     /// Empty canonical data for use in unit tests; falls back to hardcoded units.
@@ -175,7 +483,70 @@ impl CanonicalData {
             "cans".to_string(), "can".to_string(), "medium".to_string(), "small".to_string(),
             "large".to_string(), "kg".to_string(), "ml".to_string(), "g".to_string(), "l".to_string(),
         ];
-        Self { ingredients: HashMap::new(), ingredient_sections: HashMap::new(), units }
+        let unit_plurals = [("cup", "cups"), ("clove", "cloves"), ("tin", "tins"), ("can", "cans")]
+            .into_iter().map(|(s, p)| (s.to_string(), p.to_string())).collect();
+        let unit_dimensions = [
+            ("tbsp", Dimension::Volume, 15.0), ("tsp", Dimension::Volume, 5.0), ("cup", Dimension::Volume, 240.0),
+            ("ml", Dimension::Volume, 1.0), ("l", Dimension::Volume, 1000.0), ("g", Dimension::Mass, 1.0),
+            ("kg", Dimension::Mass, 1000.0), ("clove", Dimension::Count, 1.0), ("tin", Dimension::Count, 1.0),
+            ("can", Dimension::Count, 1.0), ("medium", Dimension::Count, 1.0), ("small", Dimension::Count, 1.0),
+            ("large", Dimension::Count, 1.0),
+        ].into_iter().map(|(unit, dimension, factor)| (unit.to_string(), (dimension, factor))).collect();
+        Self {
+            ingredients: HashMap::new(),
+            ingredient_sections: HashMap::new(),
+            ingredient_plurals: HashMap::new(),
+            units,
+            unit_plurals,
+            unit_dimensions,
+            ingredient_densities: HashMap::new(),
+            ingredient_nutrition: HashMap::new(),
+            ingredient_grams: HashMap::new(),
+        }
+    }
+
+    /// Singular form of a unit, so "clove" and "cloves" compare equal.
+    fn unit_singular<'a>(&'a self, unit: &'a str) -> &'a str {
+        self.unit_plurals.iter().find(|(_, plural)| *plural == unit).map_or(unit, |(singular, _)| singular)
+    }
+
+    /// The form of `unit` to write after `amount`: singular for exactly 1, plural otherwise.
+    fn unit_for_amount<'a>(&'a self, unit: &'a str, amount: f64) -> &'a str {
+        let singular = self.unit_singular(unit);
+        if amount == 1.0 {
+            singular
+        } else {
+            self.unit_plurals.get(singular).map_or(singular, String::as_str)
+        }
+    }
+
+    /// `item`, a form of an ingredient, as it reads after `amount` without a unit: "lemon" for
+    /// 1, "lemons" for 2. Items outside the vocabulary stay as written.
+    fn ingredient_for_amount<'a>(&'a self, item: &'a str, amount: f64) -> &'a str {
+        let Some(singular) = self.ingredients.get(item) else { return item };
+        if amount > 1.0 {
+            self.ingredient_plurals.get(singular).map_or(item, String::as_str)
+        } else {
+            singular
+        }
+    }
+
+    /// What `unit` measures, with grams or millilitres per unit for mass and volume.
+    fn unit_dimension(&self, unit: &str) -> Option<(Dimension, f64)> {
+        self.unit_dimensions.get(self.unit_singular(unit)).copied()
+    }
+
+    /// `amount` of `from` expressed in `to`: unchanged between forms of the same unit, exact
+    /// between two mass or two volume units, and `None` for anything else.
+    fn convert_unit(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        if self.unit_singular(from) == self.unit_singular(to) {
+            return Some(amount);
+        }
+        match (self.unit_dimension(from)?, self.unit_dimension(to)?) {
+            ((from_dimension, from_factor), (to_dimension, to_factor))
+                if from_dimension == to_dimension && from_dimension != Dimension::Count => Some(amount * from_factor / to_factor),
+            _ => None,
+        }
     }
 
     fn lookup_ingredient(&self, raw: &str) -> Option<&str> {
@@ -196,6 +567,15 @@ const VALID_CATEGORIES: &[&str] = &[
     "Spices",
 ];
 
+// Left by `import` to list guessed fields; ignored by the parser but rejected by --lint
+const REVIEW_SECTION: &str = "Review";
+
+// Allowed frontmatter values
+const VALID_DIFFICULTIES: &[&str] = &["easy", "medium", "hard"];
+const VALID_DIETS: &[&str] = &["vegan", "vegetarian", "gluten-free"];
+const VALID_CUISINES: &[&str] = &["indian", "middle-eastern", "asian", "french", "italian", "british", "american", "mediterranean"];
+const VALID_MEAL_TYPES: &[&str] = &["breakfast", "brunch", "lunch", "dinner", "dessert", "baking"];
+
 // Custom serializer to maintain category order
 fn serialize_ingredients_ordered<S>(
     ingredients: &HashMap<String, Vec<Ingredient>>,
@@ -228,6 +608,18 @@ fn unicode_fraction_value(c: char) -> Option<f64> {
     }
 }
 
+/// Text spelling of a unicode fraction, e.g. ½ → "1/2".
+fn unicode_fraction_text(c: char) -> Option<&'static str> {
+    match c {
+        '½' => Some("1/2"),
+        '⅓' => Some("1/3"),
+        '¼' => Some("1/4"),
+        '¾' => Some("3/4"),
+        '⅔' => Some("2/3"),
+        _ => None,
+    }
+}
+
 /// Parse a number from the start of a string. Returns (value, remaining_str).
 /// Handles: integers, decimals, unicode fractions, text fractions (1/2),
 /// mixed numbers with unicode (1½) and text fractions (1-3/4).
@@ -475,11 +867,14 @@ fn parse_ingredient_quantity(text: &str, known_units: &[String]) -> Option<Parse
         amount,
         amount_max,
         unit,
+        canonical_unit: None,
+        dimension: None,
         item,
         secondary_amount,
         secondary_unit,
         secondary_prefix,
         prefix,
+        imperial: None,
     })
 }
 
@@ -641,18 +1036,18 @@ fn step_ref_matches(ingredient: &Ingredient, r: &str, canonical_data: &Canonical
     }
 }
 
-/// Find ingredients not matched by any step reference.
-fn find_unreferenced_ingredients(
-    ingredients: &HashMap<String, Vec<Ingredient>>,
+/// Find ingredients not matched by any step reference, with their category.
+fn find_unreferenced_ingredients<'a>(
+    ingredients: &'a HashMap<String, Vec<Ingredient>>,
     step_refs: &[String],
     canonical_data: &CanonicalData,
-) -> Vec<String> {
+) -> Vec<(&'a str, &'a Ingredient)> {
     let mut unreferenced = Vec::new();
     for (category, items) in ingredients {
         for ingredient in items {
             let is_referenced = step_refs.iter().any(|r| step_ref_matches(ingredient, r, canonical_data));
             if !is_referenced {
-                unreferenced.push(format!("{} ({})", ingredient.text, category));
+                unreferenced.push((category.as_str(), ingredient));
             }
         }
     }
@@ -684,7 +1079,49 @@ fn find_ambiguous_refs(
     ambiguous
 }
 
-fn check_indentation(content: &str) -> Result<()> {
+/// Byte spans of frontmatter fields, used to point diagnostics at the offending line.
+struct FrontmatterSpans<'a> {
+    source: &'a str,
+    fields: HashMap<String, Range<usize>>,
+}
+
+impl<'a> FrontmatterSpans<'a> {
+    /// Scan top-level `key: value` lines of the frontmatter found at `range` in `source`.
+    /// Each span covers the value, or the key when the value is empty.
+    fn scan(source: &'a str, range: Range<usize>) -> Self {
+        let mut fields = HashMap::new();
+        let mut offset = range.start;
+        for line in source[range].split_inclusive('\n') {
+            let trimmed = line.trim_end();
+            if let Some(colon) = trimmed.find(':') {
+                let key = &trimmed[..colon];
+                if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    let value = trimmed[colon + 1..].trim_start();
+                    let span = if value.is_empty() {
+                        offset..offset + key.len()
+                    } else {
+                        let start = offset + trimmed.len() - value.len();
+                        start..offset + trimmed.len()
+                    };
+                    fields.insert(key.to_string(), span);
+                }
+            }
+            offset += line.len();
+        }
+        Self { source, fields }
+    }
+
+    fn field(&self, key: &str) -> Option<Range<usize>> {
+        self.fields.get(key).cloned()
+    }
+
+    /// Span of `needle` within a field's value, e.g. one bad entry in `diet: [vegan, keto]`.
+    fn value(&self, key: &str, needle: &str) -> Option<Range<usize>> {
+        diagnostics::narrow_span(self.source, self.fields.get(key), needle)
+    }
+}
+
+fn check_indentation(content: &str, diagnostics: &mut Diagnostics) {
     let indented_lines = content.lines()
         .filter(|line| !line.trim().is_empty())
         .filter(|line| !line.starts_with("---"))
@@ -695,39 +1132,97 @@ fn check_indentation(content: &str) -> Result<()> {
         .filter(|line| !line.starts_with("---"))
         .count();
     if total_lines > 0 && indented_lines == total_lines {
-        bail!(
+        // Strip the indentation common to every line, keeping nested lists nested
+        let indent = content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter(|line| !line.starts_with("---"))
+            .map(|line| line.len() - line.trim_start_matches(' ').len())
+            .min()
+            .unwrap_or(0);
+        let mut edits = Vec::new();
+        let mut line_start = 0;
+        for line in content.split_inclusive('\n') {
+            if !line.trim().is_empty() && line.starts_with(' ') {
+                edits.push(diagnostics::Edit { span: line_start..line_start + indent, replacement: String::new() });
+            }
+            line_start += line.len();
+        }
+        diagnostics.error("indentation", None,
             "Every line in your recipe starts with extra spaces.\n  \
             This usually happens when copy-pasting from a website or editor.\n  \
             Please remove the leading spaces from all lines and try again."
-        );
+        ).with_fix(format!("removed {} leading space(s) from {} line(s)", indent, edits.len()), edits);
     }
-    Ok(())
 }
 
-fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> Result<Recipe> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read file: {:?}", path))?;
+/// The full line(s) containing `span`, including the trailing newline. When the lines sit
+/// between two blank lines (or at the start of the document), one of the blanks goes too so
+/// removing them doesn't leave a double gap.
+fn removable_lines(content: &str, span: &Range<usize>) -> Range<usize> {
+    let start = content[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let last = span.end.max(span.start + 1) - 1;
+    let mut end = content[last..].find('\n').map_or(content.len(), |i| last + i + 1);
+    let blank_before = start == 0 || content[..start - 1].rsplit('\n').next().is_some_and(|l| l.trim().is_empty());
+    if blank_before {
+        if let Some(next) = content[end..].split_inclusive('\n').next() {
+            if next.trim().is_empty() {
+                end += next.len();
+            }
+        }
+    }
+    start..end
+}
 
-    // Check for copy-paste indentation before parsing
-    check_indentation(&content)?;
+/// Parse a recipe's markdown source, pushing every problem found into `diagnostics`.
+/// Diagnostic spans are byte offsets into `content`.
+/// Returns None when the recipe could not be parsed or any error was reported.
+fn parse_recipe(
+    content: &str,
+    lint: bool,
+    canonical: &CanonicalData,
+    diagnostics: &mut Diagnostics,
+) -> Option<Recipe> {
+    // Check for copy-paste indentation before parsing; everything else would be noise
+    check_indentation(content, diagnostics);
+    if diagnostics.has_errors() {
+        return None;
+    }
 
     // Split frontmatter and content
     let parts: Vec<&str> = content.splitn(3, "---").collect();
     if parts.len() < 3 {
-        bail!("Invalid recipe format: missing frontmatter delimiters");
+        diagnostics.error("frontmatter-delimiters", None, "Invalid recipe format: missing frontmatter delimiters");
+        return None;
     }
+    let frontmatter_start = parts[0].len() + 3;
+    let frontmatter_range = frontmatter_start..frontmatter_start + parts[1].len();
+    let markdown_start = frontmatter_range.end + 3;
 
     // Parse frontmatter
-    let frontmatter: RecipeFrontmatter = serde_yaml::from_str(parts[1].trim())
-        .map_err(|e| friendly_frontmatter_error(&e))?;
+    let frontmatter_text = parts[1].trim();
+    let frontmatter_offset = frontmatter_start + (parts[1].len() - parts[1].trim_start().len());
+    let frontmatter: RecipeFrontmatter = match serde_yaml::from_str(frontmatter_text) {
+        Ok(frontmatter) => frontmatter,
+        Err(e) => {
+            let span = yaml_error_offset(&e, frontmatter_text).map(|index| {
+                let start = frontmatter_offset + index;
+                let line_end = content[start..].find('\n').map(|i| start + i).unwrap_or(content.len());
+                start..line_end.max(start + 1).min(content.len())
+            });
+            diagnostics.error("frontmatter-syntax", span, friendly_frontmatter_error(&e).to_string());
+            return None;
+        }
+    };
 
     // Validate frontmatter
-    validate_frontmatter(&frontmatter, lint)?;
+    let frontmatter_spans = FrontmatterSpans::scan(content, frontmatter_range);
+    validate_frontmatter(&frontmatter, lint, &frontmatter_spans, diagnostics);
 
     let markdown_content = parts[2].trim();
+    let markdown_offset = markdown_start + (parts[2].len() - parts[2].trim_start().len());
 
-    // Parse markdown content
-    let parser = MarkdownParser::new(markdown_content);
+    // Parse markdown content, tracking source offsets for diagnostics
+    let parser = MarkdownParser::new(markdown_content).into_offset_iter();
 
     let mut current_section = String::new();
     let mut flat_ingredients: Vec<Ingredient> = Vec::new();
@@ -742,7 +1237,14 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
 
     let mut current_heading_level = 0;
 
-    for event in parser {
+    // Spans of the current block's text, each ingredient line (by id) and each step
+    let mut current_span: Option<Range<usize>> = None;
+    let mut heading_span: Option<Range<usize>> = None;
+    let mut ingredient_spans: HashMap<u32, Range<usize>> = HashMap::new();
+    let mut step_spans: Vec<Range<usize>> = Vec::new();
+
+    for (event, range) in parser {
+        let range = range.start + markdown_offset..range.end + markdown_offset;
         match event {
             Event::Start(Tag::Heading { level, .. }) => {
                 current_heading_level = match level {
@@ -752,9 +1254,16 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
                     _ => 0,
                 };
                 current_text.clear();
+                heading_span = Some(range);
             }
             Event::End(TagEnd::Heading(_)) if current_heading_level == 1 && !current_text.is_empty() => {
                 current_section = current_text.trim().to_string();
+                if lint && current_section == REVIEW_SECTION {
+                    diagnostics.error("review-section", heading_span.clone(), format!(
+                        "Check the items under '# {}' and then delete the section",
+                        REVIEW_SECTION
+                    ));
+                }
                 current_text.clear();
                 current_heading_level = 0;
             }
@@ -762,11 +1271,16 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
                 // Section headers (## Fresh, ## Pantry, etc.) are no longer used.
                 // Sections are derived from ingredients.json.
                 if lint {
-                    bail!(
+                    let span = heading_span.take();
+                    let diagnostic = diagnostics.error("section-header", span.clone(), format!(
                         "Ingredient section headers ('## {}') are no longer supported. \
                          Remove section headers from recipe files — sections are derived from ingredients.json.",
                         current_text.trim()
-                    );
+                    ));
+                    if let Some(span) = span {
+                        let edit = diagnostics::Edit { span: removable_lines(content, &span), replacement: String::new() };
+                        diagnostic.with_fix(format!("removed '## {}'", current_text.trim()), vec![edit]);
+                    }
                 }
                 current_text.clear();
                 current_heading_level = 0;
@@ -780,23 +1294,25 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
             }
             Event::Start(Tag::Item) => {
                 current_text.clear();
+                current_span = None;
                 current_ingredient_no_scale = false;
             }
             Event::End(TagEnd::Item) if in_list && current_section == "Ingredients" => {
                 // Strip any no-scale annotation from text (in case parser included it)
                 let raw_text = current_text.trim()
                     .replace("<!-- no-scale -->", "").trim().to_string();
+                let item_span = current_span.take();
                 if !raw_text.is_empty() {
                     // Extract [canonical] tag and preparation from text
                     let (clean_text, raw_canonical, preparation) = strip_canonical(&raw_text);
 
                     // Lint: error if no [canonical] tag present
                     if lint && raw_canonical.is_none() {
-                        bail!(
+                        diagnostics.error("missing-canonical", item_span.clone(), format!(
                             "Ingredient '{}' is missing a [canonical] tag. \
                             Wrap the ingredient name in brackets, e.g. [garlic].",
                             raw_text
-                        );
+                        ));
                     }
 
                     // Resolve canonical to singular form via vocabulary
@@ -807,22 +1323,22 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
                     // Lint: error if [canonical] is not in the vocabulary or has no section
                     if lint {
                         if let Some(raw) = &raw_canonical {
+                            let tag_span = diagnostics::narrow_span(content, item_span.as_ref(), &format!("[{}]", raw));
                             if canonical.lookup_ingredient(raw).is_none() && !canonical.ingredients.is_empty() {
-                                bail!(
+                                diagnostics.error("unknown-canonical", tag_span, format!(
                                     "Ingredient '{}': [{}] is not in ingredients.json. \
                                     Add it to docs/ingredients.json before linting.",
                                     raw_text, raw
-                                );
-                            }
-                            if let Some(resolved) = &canonical_resolved {
+                                ));
+                            } else if let Some(resolved) = &canonical_resolved {
                                 if !canonical.ingredient_sections.is_empty()
                                     && canonical.lookup_section(resolved).is_none()
                                 {
-                                    bail!(
+                                    diagnostics.error("canonical-section", tag_span, format!(
                                         "Ingredient '{}': canonical '{}' has no section in ingredients.json. \
                                         Add a 'section' field to its entry.",
                                         clean_text, resolved
-                                    );
+                                    ));
                                 }
                             }
                         }
@@ -836,15 +1352,28 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
                         if let (Some(ref mut qty), Some(ref raw)) = (&mut q, &raw_canonical) {
                             qty.item = raw.clone();
                         }
+                        if let Some(qty) = &mut q {
+                            if let Some(unit) = &qty.unit {
+                                qty.canonical_unit = Some(canonical.unit_singular(unit).to_string());
+                                qty.dimension = canonical.unit_dimension(unit).map(|(dimension, _)| dimension);
+                            }
+                        }
                         // Lint warning: ingredient looks scalable but failed to parse
                         if lint && q.is_none() {
                             let first_char = clean_text.chars().next().unwrap_or(' ');
                             if first_char.is_ascii_digit() || unicode_fraction_value(first_char).is_some() {
-                                eprintln!("  \u{26a0}\u{fe0f}  WARNING: Ingredient '{}' starts with a number but could not be parsed for scaling. Add <!-- no-scale --> to suppress.", clean_text);
+                                diagnostics.warning("unparseable-quantity", item_span.clone(), format!(
+                                    "Ingredient '{}' starts with a number but could not be parsed for scaling. \
+                                    Add <!-- no-scale --> to suppress.",
+                                    clean_text
+                                ));
                             }
                         }
                         q
                     };
+                    if let Some(span) = item_span {
+                        ingredient_spans.insert(ingredient_id, span);
+                    }
                     flat_ingredients.push(Ingredient {
                         id: ingredient_id,
                         text: clean_text,
@@ -862,6 +1391,7 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
                 if !text.is_empty() {
                     let durations = parse_step_durations(&text);
                     steps.push(Step { text, durations });
+                    step_spans.push(current_span.take().unwrap_or(range));
                 }
                 current_text.clear();
             }
@@ -890,17 +1420,19 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
             }
             Event::Text(text) => {
                 current_text.push_str(&text);
+                extend_span(&mut current_span, range);
             }
             Event::Code(code) => {
                 current_text.push_str(&code);
+                extend_span(&mut current_span, range);
             }
             Event::SoftBreak | Event::HardBreak => {
                 current_text.push(' ');
             }
-            Event::Html(html) | Event::InlineHtml(html) => {
-                if in_list && current_section == "Ingredients" && html.contains("no-scale") {
-                    current_ingredient_no_scale = true;
-                }
+            Event::Html(html) | Event::InlineHtml(html)
+                if in_list && current_section == "Ingredients" && html.contains("no-scale") =>
+            {
+                current_ingredient_no_scale = true;
             }
             _ => {}
         }
@@ -918,60 +1450,59 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
 
     // Validation
     if ingredients.is_empty() {
-        bail!("Recipe must have at least one ingredient");
+        diagnostics.error("missing-ingredients", None, "Recipe must have at least one ingredient");
     }
     if steps.is_empty() {
-        bail!("Recipe must have at least one instruction step");
+        diagnostics.error("missing-steps", None, "Recipe must have at least one instruction step");
     }
 
     // Lint mode: check for empty ingredients and steps
     if lint {
-        for (category, items) in &ingredients {
-            for item in items {
+        for &category in VALID_CATEGORIES {
+            for item in ingredients.get(category).into_iter().flatten() {
+                let span = ingredient_spans.get(&item.id);
                 if item.text.trim().is_empty() {
-                    bail!("Empty ingredient found in category '{}'", category);
+                    diagnostics.error("empty-ingredient", span.cloned(), format!("Empty ingredient found in category '{}'", category));
                 }
                 // Check for improper spacing between numbers and units (SI standard)
-                validate_unit_spacing(&item.text, category)?;
+                validate_unit_spacing(&item.text, category, content, span, diagnostics);
                 // Check for unicode fractions (should use text fractions like 1/2 instead)
-                validate_no_unicode_fractions(&item.text, category)?;
+                validate_no_unicode_fractions(&item.text, category, content, span, diagnostics);
             }
         }
         for (idx, step) in steps.iter().enumerate() {
             if step.text.trim().is_empty() {
-                bail!("Empty instruction step found at position {}", idx + 1);
+                diagnostics.error("empty-step", step_spans.get(idx).cloned(), format!("Empty instruction step found at position {}", idx + 1));
             }
         }
 
-        // Check for unreferenced ingredients
+        // Check for unreferenced ingredients (non-blocking)
         let all_steps_text = steps.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ").to_lowercase();
         let step_refs = extract_step_refs(&all_steps_text);
-        let unreferenced_ingredients = find_unreferenced_ingredients(&ingredients, &step_refs, canonical);
-
-        // Print warnings for unreferenced ingredients (non-blocking)
-        if !unreferenced_ingredients.is_empty() {
-            eprintln!("\n⚠️  WARNING: The following ingredients are not linked in any instruction step:");
-            for ingredient in &unreferenced_ingredients {
-                eprintln!("   - {}", ingredient);
-            }
-            eprintln!("   Consider adding {{ingredient}} references in your steps for better UX.\n");
+        for (category, ingredient) in find_unreferenced_ingredients(&ingredients, &step_refs, canonical) {
+            diagnostics.warning("unreferenced-ingredient", ingredient_spans.get(&ingredient.id).cloned(), format!(
+                "Ingredient '{} ({})' is not linked in any instruction step. \
+                Consider adding {{ingredient}} references in your steps for better UX.",
+                ingredient.text, category
+            ));
         }
 
-        // Check for ambiguous refs (blocking)
-        let ambiguous_refs = find_ambiguous_refs(&ingredients, &step_refs, canonical);
-        if !ambiguous_refs.is_empty() {
-            let details: Vec<String> = ambiguous_refs
-                .iter()
-                .map(|(r, matches)| format!("  {{{}}} matches: {}", r, matches.join(", ")))
-                .collect();
-            bail!(
-                "Ambiguous ingredient references found:\n{}",
-                details.join("\n")
-            );
+        // Check for ambiguous refs (blocking), pointing at the first step that uses them
+        for (r, matches) in find_ambiguous_refs(&ingredients, &step_refs, canonical) {
+            let needle = format!("{{{}}}", r);
+            let span = step_spans.iter().find_map(|s| diagnostics::find_span(content, s, &needle));
+            diagnostics.error("ambiguous-ref", span, format!(
+                "Ambiguous ingredient reference {{{}}} matches: {}",
+                r, matches.join(", ")
+            ));
         }
     }
 
-    Ok(Recipe {
+    if diagnostics.has_errors() {
+        return None;
+    }
+
+    let mut recipe = Recipe {
         id: frontmatter.id,
         name: frontmatter.name,
         description: frontmatter.description,
@@ -987,10 +1518,27 @@ fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> R
         steps,
         serving_suggestions,
         tested: frontmatter.tested.unwrap_or(true),
-    })
+        nutrition: None,
+    };
+    recipe.nutrition = nutrition::estimate(&recipe, canonical).nutrition;
+    Some(recipe)
+}
+
+/// Grow `span` to cover `range`, starting it if empty.
+fn extend_span(span: &mut Option<Range<usize>>, range: Range<usize>) {
+    *span = Some(match span.take() {
+        Some(existing) => existing.start.min(range.start)..existing.end.max(range.end),
+        None => range,
+    });
 }
 
-fn validate_unit_spacing(text: &str, category: &str) -> Result<()> {
+fn validate_unit_spacing(
+    text: &str,
+    category: &str,
+    source: &str,
+    span: Option<&Range<usize>>,
+    diagnostics: &mut Diagnostics,
+) {
     // Check for numbers directly followed by metric units without space
     // Matches patterns like: 500g, 200ml, 1.5kg, 25l
     let metric_units = ["g", "kg", "ml", "l"];
@@ -1011,33 +1559,75 @@ fn validate_unit_spacing(text: &str, category: &str) -> Result<()> {
                         // Check if the whole prefix is a valid number (including decimals)
                         let is_number = before_unit.chars().all(|c| c.is_ascii_digit() || c == '.');
                         if is_number {
-                            bail!(
+                            let diagnostic = diagnostics.error("unit-spacing", diagnostics::narrow_span(source, span, cleaned), format!(
                                 "Improper unit spacing in '{}' (category '{}'): '{}' should be '{} {}'. \
                                 Per UK/SI standards, there must be a space between the number and unit.",
                                 text, category, cleaned, before_unit, unit
-                            );
+                            ));
+                            if let Some(exact) = diagnostics::find_exact(source, span, cleaned) {
+                                let replacement = format!("{} {}", before_unit, unit);
+                                let label = format!("'{}' → '{}'", cleaned, replacement);
+                                diagnostic.with_fix(label, vec![diagnostics::Edit { span: exact, replacement }]);
+                            }
                         }
                     }
                 }
             }
         }
     }
-
-    Ok(())
 }
 
-fn validate_no_unicode_fractions(text: &str, category: &str) -> Result<()> {
+fn validate_no_unicode_fractions(
+    text: &str,
+    category: &str,
+    source: &str,
+    span: Option<&Range<usize>>,
+    diagnostics: &mut Diagnostics,
+) {
     for c in text.chars() {
-        if unicode_fraction_value(c).is_some() {
-            bail!(
+        if let Some(fraction) = unicode_fraction_text(c) {
+            let needle = c.encode_utf8(&mut [0; 4]).to_string();
+            let fraction_span = diagnostics::narrow_span(source, span, &needle);
+            let diagnostic = diagnostics.error("unicode-fraction", fraction_span, format!(
                 "Unicode fraction '{}' in '{}' (category '{}'). Use text fractions instead (e.g., 1/2 not ½).",
                 c, text, category
-            );
+            ));
+            if let Some(exact) = diagnostics::find_exact(source, span, &needle) {
+                // Mixed numbers are written with a dash: 1½ → 1-1/2
+                let after_digit = source[..exact.start].ends_with(|p: char| p.is_ascii_digit());
+                let replacement = if after_digit { format!("-{}", fraction) } else { fraction.to_string() };
+                let label = format!("'{}' → '{}'", c, replacement);
+                diagnostic.with_fix(label, vec![diagnostics::Edit { span: exact, replacement }]);
+            }
         }
     }
-    Ok(())
 }
 
+/// Byte offset into the frontmatter text that a YAML error refers to.
+/// Scanner errors point where the parser gave up; the "while scanning ... at line N column M"
+/// context usually names the line the author actually broke, so prefer it.
+fn yaml_error_offset(err: &serde_yaml::Error, text: &str) -> Option<usize> {
+    let msg = err.to_string();
+    // Missing fields are reported at the start of the document, which isn't helpful
+    if msg.starts_with("missing field") {
+        return None;
+    }
+    if let Some(context) = msg.split(", while scanning").nth(1) {
+        let numbers: Vec<usize> = context
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|n| n.parse().ok())
+            .collect();
+        if let [line, column, ..] = numbers[..] {
+            let line_start: usize = text.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+            let line_text = text[line_start..].lines().next().unwrap_or_default();
+            let column_offset: usize = line_text.chars().take(column.saturating_sub(1)).map(char::len_utf8).sum();
+            return Some(line_start + column_offset);
+        }
+    }
+    err.location().map(|loc| loc.index()).filter(|&i| i <= text.len())
+}
+
+#[allow(clippy::collapsible_if)]
 fn friendly_frontmatter_error(err: &serde_yaml::Error) -> anyhow::Error {
     let msg = err.to_string();
 
@@ -1102,52 +1692,56 @@ fn friendly_frontmatter_error(err: &serde_yaml::Error) -> anyhow::Error {
     anyhow::anyhow!("Could not read the recipe header: {}", msg)
 }
 
-fn validate_frontmatter(fm: &RecipeFrontmatter, lint: bool) -> Result<()> {
+fn validate_frontmatter(
+    fm: &RecipeFrontmatter,
+    lint: bool,
+    spans: &FrontmatterSpans,
+    diagnostics: &mut Diagnostics,
+) {
     // Validate ID format and length
     if fm.id.is_empty() {
-        bail!("Recipe ID cannot be empty");
+        diagnostics.error("id-format", spans.field("id"), "Recipe ID cannot be empty");
     }
     if fm.id.len() > 100 {
-        bail!("Recipe ID too long (max 100 characters): '{}'", fm.id);
+        diagnostics.error("id-format", spans.field("id"), format!("Recipe ID too long (max 100 characters): '{}'", fm.id));
     }
     if !fm.id.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
-        bail!("Recipe ID can only contain lowercase letters and dashes: '{}'\n  Example: thai-green-curry", fm.id);
+        diagnostics.error("id-format", spans.field("id"), format!("Recipe ID can only contain lowercase letters and dashes: '{}'\n  Example: thai-green-curry", fm.id));
     }
     if fm.id.starts_with('-') || fm.id.ends_with('-') {
-        bail!("Recipe ID cannot start or end with a dash: '{}'", fm.id);
+        diagnostics.error("id-format", spans.field("id"), format!("Recipe ID cannot start or end with a dash: '{}'", fm.id));
     }
     if fm.id.contains("--") {
-        bail!("Recipe ID cannot contain consecutive dashes: '{}'", fm.id);
+        diagnostics.error("id-format", spans.field("id"), format!("Recipe ID cannot contain consecutive dashes: '{}'", fm.id));
     }
 
     // Validate name length
     if fm.name.is_empty() {
-        bail!("Recipe name cannot be empty");
+        diagnostics.error("name-length", spans.field("name"), "Recipe name cannot be empty");
     }
     if fm.name.len() > 200 {
-        bail!("Recipe name too long (max 200 characters)");
+        diagnostics.error("name-length", spans.field("name"), "Recipe name too long (max 200 characters)");
     }
 
     // Validate description length
     if fm.description.is_empty() {
-        bail!("Recipe description cannot be empty");
+        diagnostics.error("description-length", spans.field("description"), "Recipe description cannot be empty");
     }
     if fm.description.len() > 500 {
-        bail!("Recipe description too long (max 500 characters)");
+        diagnostics.error("description-length", spans.field("description"), "Recipe description too long (max 500 characters)");
     }
 
     // Validate difficulty
-    let valid_difficulties = ["easy", "medium", "hard"];
-    if !valid_difficulties.contains(&fm.difficulty.as_str()) {
-        bail!("Difficulty '{}' is not valid. Use one of: easy, medium, or hard", fm.difficulty);
+    if !VALID_DIFFICULTIES.contains(&fm.difficulty.as_str()) {
+        diagnostics.error("difficulty", spans.field("difficulty"), format!("Difficulty '{}' is not valid. Use one of: easy, medium, or hard", fm.difficulty));
     }
 
     // Validate numeric fields
     if fm.servings == 0 {
-        bail!("Servings must be greater than 0");
+        diagnostics.error("servings", spans.field("servings"), "Servings must be greater than 0");
     }
     if fm.time == 0 {
-        bail!("Time must be greater than 0");
+        diagnostics.error("time", spans.field("time"), "Time must be greater than 0");
     }
 
     // Validate optional fields (always, not just lint mode)
@@ -1157,60 +1751,56 @@ fn validate_frontmatter(fm: &RecipeFrontmatter, lint: bool) -> Result<()> {
             _ => c.is_ascii_digit(),
         });
     if !valid_date {
-        bail!("Date must be in YYYY-MM-DD format, got '{}'", fm.date);
+        diagnostics.error("date-format", spans.field("date"), format!("Date must be in YYYY-MM-DD format, got '{}'", fm.date));
     }
 
     // Validate diet values (always, not just lint mode)
     if fm.diet.is_empty() {
-        bail!("Diet must have at least one value. Valid values: vegan, vegetarian, gluten-free");
+        diagnostics.error("diet", spans.field("diet"), "Diet must have at least one value. Valid values: vegan, vegetarian, gluten-free");
     }
-    let valid_diets = ["vegan", "vegetarian", "gluten-free"];
     for d in &fm.diet {
-        if !valid_diets.contains(&d.as_str()) {
-            bail!("Invalid diet value: '{}'. Valid values: {:?}", d, valid_diets);
+        if !VALID_DIETS.contains(&d.as_str()) {
+            diagnostics.error("diet", spans.value("diet", d), format!("Invalid diet value: '{}'. Valid values: {:?}", d, VALID_DIETS));
         }
     }
 
     // Validate cuisine values
     if fm.cuisine.is_empty() {
-        bail!("cuisine must have at least one value. Valid values: indian, middle-eastern, asian, french, italian, british, american, mediterranean");
+        diagnostics.error("cuisine", spans.field("cuisine"), "cuisine must have at least one value. Valid values: indian, middle-eastern, asian, french, italian, british, american, mediterranean");
     }
-    let valid_cuisines = ["indian", "middle-eastern", "asian", "french", "italian", "british", "american", "mediterranean"];
     for c in &fm.cuisine {
-        if !valid_cuisines.contains(&c.as_str()) {
-            bail!("Invalid cuisine value: '{}'. Valid values: {:?}", c, valid_cuisines);
+        if !VALID_CUISINES.contains(&c.as_str()) {
+            diagnostics.error("cuisine", spans.value("cuisine", c), format!("Invalid cuisine value: '{}'. Valid values: {:?}", c, VALID_CUISINES));
         }
     }
 
     // Validate meal_type values
     if fm.meal_type.is_empty() {
-        bail!("meal_type must have at least one value. Valid values: breakfast, brunch, lunch, dinner, dessert, baking");
+        diagnostics.error("meal-type", spans.field("meal_type"), "meal_type must have at least one value. Valid values: breakfast, brunch, lunch, dinner, dessert, baking");
     }
-    let valid_meal_types = ["breakfast", "brunch", "lunch", "dinner", "dessert", "baking"];
     for m in &fm.meal_type {
-        if !valid_meal_types.contains(&m.as_str()) {
-            bail!("Invalid meal_type value: '{}'. Valid values: {:?}", m, valid_meal_types);
+        if !VALID_MEAL_TYPES.contains(&m.as_str()) {
+            diagnostics.error("meal-type", spans.value("meal_type", m), format!("Invalid meal_type value: '{}'. Valid values: {:?}", m, VALID_MEAL_TYPES));
         }
     }
 
     if lint {
         // Additional strict checks for linting mode
-        if fm.name.len() < 3 {
-            bail!("Name too short (minimum 3 characters)");
+        // Empty values are already reported above
+        if !fm.name.is_empty() && fm.name.len() < 3 {
+            diagnostics.error("name-length", spans.field("name"), "Name too short (minimum 3 characters)");
         }
-        if fm.description.len() < 10 {
-            bail!("Description too short (minimum 10 characters)");
+        if !fm.description.is_empty() && fm.description.len() < 10 {
+            diagnostics.error("description-length", spans.field("description"), "Description too short (minimum 10 characters)");
         }
         // Reasonable ranges
         if fm.servings > 100 {
-            bail!("Servings seems unreasonably high: {} (max 100)", fm.servings);
+            diagnostics.error("servings", spans.field("servings"), format!("Servings seems unreasonably high: {} (max 100)", fm.servings));
         }
         if fm.time > 1440 {
-            bail!("Time seems unreasonably long: {} minutes (max 24 hours)", fm.time);
+            diagnostics.error("time", spans.field("time"), format!("Time seems unreasonably long: {} minutes (max 24 hours)", fm.time));
         }
     }
-
-    Ok(())
 }
 
 fn escape_html(s: &str) -> String {
@@ -1220,162 +1810,669 @@ fn escape_html(s: &str) -> String {
         .replace('"', "&quot;")
 }
 
-fn generate_og_html(recipe: &Recipe) -> String {
+/// Step text as readers see it: `{garlic}` → `garlic`.
+fn strip_step_refs(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        match rest[open..].find('}') {
+            Some(close) => {
+                out.push_str(&rest[..open]);
+                out.push_str(&rest[open + 1..open + close]);
+                rest = &rest[open + close + 1..];
+            }
+            None => break,
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Minutes as an ISO 8601 duration, e.g. 90 → "PT1H30M".
+fn iso_duration(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("PT{}M", m),
+        (h, 0) => format!("PT{}H", h),
+        (h, m) => format!("PT{}H{}M", h, m),
+    }
+}
+
+/// Where the site is published; structured data, social previews, sitemap and feed entries
+/// need absolute URLs.
+const SITE_URL: &str = "https://biteme.ovh";
+
+/// schema.org Recipe structured data for search engine rich results.
+fn recipe_json_ld(recipe: &Recipe) -> serde_json::Value {
+    let mut ingredients: Vec<&Ingredient> = recipe.ingredients.values().flatten().collect();
+    ingredients.sort_by_key(|i| i.id);
+    let diets: Vec<&str> = recipe.diet.iter().filter_map(|d| match d.as_str() {
+        "vegan" => Some("https://schema.org/VeganDiet"),
+        "vegetarian" => Some("https://schema.org/VegetarianDiet"),
+        "gluten-free" => Some("https://schema.org/GlutenFreeDiet"),
+        _ => None,
+    }).collect();
+    let steps: Vec<serde_json::Value> = recipe.steps.iter().enumerate().map(|(i, step)| serde_json::json!({
+        "@type": "HowToStep",
+        "position": i + 1,
+        "text": strip_step_refs(&step.text),
+    })).collect();
+
+    serde_json::json!({
+        "@context": "https://schema.org",
+        "@type": "Recipe",
+        "name": recipe.name,
+        "description": recipe.description,
+        "image": format!("{}/assets/icons/icon-512.png", SITE_URL),
+        "url": format!("{}/r/{}.html", SITE_URL, recipe.id),
+        "author": { "@type": "Organization", "name": "BiteMe" },
+        "datePublished": recipe.date,
+        "totalTime": iso_duration(recipe.time),
+        "recipeYield": format!("{} servings", recipe.servings),
+        "recipeCuisine": recipe.cuisine,
+        "recipeCategory": recipe.meal_type,
+        "suitableForDiet": diets,
+        "recipeIngredient": ingredients.iter().map(|i| i.text.as_str()).collect::<Vec<_>>(),
+        "recipeInstructions": steps,
+    })
+}
+
+/// Text paragraphs (separated by blank lines) as escaped `<p>` elements.
+fn html_paragraphs(text: &str) -> String {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("\n        <p>{}</p>", escape_html(p)))
+        .collect()
+}
+
+/// The shareable `docs/r/<id>.html` page: the full recipe as static HTML, readable by
+/// crawlers and without JavaScript, with OG tags for link previews and a link into the app.
+fn generate_recipe_html(recipe: &Recipe) -> String {
     let name = escape_html(&recipe.name);
     let description = escape_html(&recipe.description);
     let id = &recipe.id;
+    // Escaping '<' keeps recipe text from closing the script element
+    let json_ld = serde_json::to_string_pretty(&recipe_json_ld(recipe))
+        .expect("JSON-LD serializes")
+        .replace('<', "\\u003c")
+        .replace('\n', "\n  ");
+
+    let tags: String = recipe.meal_type.iter()
+        .map(|m| format!(r#"<a href="/index.html?meal_type={0}" class="tag tag-meal-type">{0}</a>"#, escape_html(m)))
+        .chain(recipe.cuisine.iter()
+            .map(|c| format!(r#"<a href="/index.html?cuisine={0}" class="tag tag-cuisine">{0}</a>"#, escape_html(c))))
+        .collect();
+    let ingredients: String = VALID_CATEGORIES.iter()
+        .filter_map(|&category| recipe.ingredients.get(category).filter(|items| !items.is_empty()).map(|items| (category, items)))
+        .map(|(category, items)| {
+            let items: String = items.iter().map(|i| format!("\n            <li>{}</li>", escape_html(&i.text))).collect();
+            format!("\n        <div class=\"ingredient-category\">\n          <h4>{}</h4>\n          <ul>{}\n          </ul>\n        </div>", category, items)
+        })
+        .collect();
+    let steps: String = recipe.steps.iter()
+        .map(|step| format!("\n          <li>{}</li>", escape_html(&strip_step_refs(&step.text))))
+        .collect();
+    let section = |class: &str, title: &str, text: &Option<String>| match text {
+        Some(text) => format!("\n\n      <section class=\"{}\">\n        <h3>{}</h3>{}\n      </section>", class, title, html_paragraphs(text)),
+        None => String::new(),
+    };
+    let notes = section("recipe-notes", "Notes", &recipe.notes);
+    let serving_suggestions = section("serving-suggestions", "Serving Suggestions", &recipe.serving_suggestions);
+
     format!(
-        r#"<!DOCTYPE html>
+        r##"<!DOCTYPE html>
 <html lang="en">
 <head>
   <meta charset="UTF-8">
   <meta name="viewport" content="width=device-width, initial-scale=1.0">
+  <meta name="theme-color" content="#6B9080">
   <meta name="description" content="{description}">
   <meta property="og:type" content="article">
   <meta property="og:site_name" content="BiteMe">
   <meta property="og:title" content="{name} — BiteMe">
   <meta property="og:description" content="{description}">
-  <meta property="og:image" content="https://biteme.ovh/assets/icons/icon-512.png">
-  <meta property="og:url" content="https://biteme.ovh/r/{id}.html">
+  <meta property="og:image" content="{site}/assets/icons/icon-512.png">
+  <meta property="og:url" content="{site}/r/{id}.html">
   <meta name="twitter:card" content="summary">
   <meta name="twitter:title" content="{name} — BiteMe">
   <meta name="twitter:description" content="{description}">
-  <meta name="twitter:image" content="https://biteme.ovh/assets/icons/icon-512.png">
+  <meta name="twitter:image" content="{site}/assets/icons/icon-512.png">
+  <link rel="icon" type="image/svg+xml" href="/assets/icons/favicon.svg">
+  <link rel="alternate" type="application/atom+xml" title="BiteMe — New Recipes" href="/feed.xml">
   <title>{name} — BiteMe</title>
+  <link rel="stylesheet" href="/css/style.css">
+  <link rel="stylesheet" href="/css/recipe.css">
+  <script type="application/ld+json">
+  {json_ld}
+  </script>
 </head>
 <body>
-  <p>{name} — {description}</p>
-  <script>window.location.replace('/recipe.html?id={id}');</script>
+  <header>
+    <nav class="recipe-nav">
+      <h1><a href="/" class="logo">bite<span class="logo-accent">me</span></a></h1>
+    </nav>
+  </header>
+
+  <main id="recipe-detail">
+    <article class="recipe">
+      <div class="recipe-header">
+        <h2 class="recipe-name">{name}</h2>
+      </div>
+      <p class="recipe-description">{description}</p>
+      <p class="cooking-stats">{servings} servings · {time} min · {difficulty}</p>
+      <div class="recipe-tags">{tags}</div>
+
+      <section class="ingredients">
+        <h3>Ingredients</h3>{ingredients}
+      </section>
+
+      <section class="instructions">
+        <h3>Instructions</h3>
+        <ol>{steps}
+        </ol>
+      </section>{notes}{serving_suggestions}
+
+      <div class="action-buttons">
+        <a href="/recipe.html?id={id}" class="button button-primary">Open in BiteMe</a>
+      </div>
+    </article>
+  </main>
 </body>
 </html>
-"#
+"##,
+        site = SITE_URL,
+        servings = recipe.servings,
+        time = recipe.time,
+        difficulty = escape_html(&recipe.difficulty),
     )
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    println!("🔍 Parsing recipes from: {:?}", cli.input);
+/// How many of the newest recipes `feed.xml` lists.
+const FEED_ENTRIES: usize = 20;
 
-    if !cli.input.exists() {
-        bail!("Input directory does not exist: {:?}", cli.input);
+/// `docs/sitemap.xml`: the home page and every recipe page. `recipes` is newest first.
+fn generate_sitemap(recipes: &[Recipe]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    let url = |loc: String, lastmod: Option<&str>| match lastmod {
+        Some(date) => format!("  <url>\n    <loc>{}</loc>\n    <lastmod>{}</lastmod>\n  </url>\n", loc, escape_html(date)),
+        None => format!("  <url>\n    <loc>{}</loc>\n  </url>\n", loc),
+    };
+    xml.push_str(&url(format!("{}/", SITE_URL), recipes.first().map(|r| r.date.as_str())));
+    for recipe in recipes {
+        xml.push_str(&url(format!("{}/r/{}.html", SITE_URL, recipe.id), Some(&recipe.date)));
     }
+    xml.push_str("</urlset>\n");
+    xml
+}
 
-    // Load canonical vocabulary
-    let canonical_path = std::path::Path::new("docs/ingredients.json");
-    let canonical = if canonical_path.exists() {
-        CanonicalData::load(canonical_path)?
+/// `docs/feed.xml`: an Atom feed of the newest recipes. `recipes` is newest first.
+fn generate_feed(recipes: &[Recipe]) -> String {
+    // Recipes only have a date; Atom wants a full timestamp
+    let timestamp = |date: &str| format!("{}T00:00:00Z", escape_html(date));
+    let updated = recipes.first().map(|r| timestamp(&r.date)).unwrap_or_default();
+    let mut xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>BiteMe — New Recipes</title>
+  <subtitle>The latest vegetarian and vegan recipes on BiteMe</subtitle>
+  <link href="{site}/feed.xml" rel="self"/>
+  <link href="{site}/"/>
+  <id>{site}/</id>
+  <updated>{updated}</updated>
+  <author>
+    <name>BiteMe</name>
+  </author>
+"#,
+        site = SITE_URL,
+    );
+    for recipe in recipes.iter().take(FEED_ENTRIES) {
+        let url = format!("{}/r/{}.html", SITE_URL, recipe.id);
+        xml.push_str(&format!(
+            r#"  <entry>
+    <title>{name}</title>
+    <link href="{url}"/>
+    <id>{url}</id>
+    <published>{date}</published>
+    <updated>{date}</updated>
+    <summary>{description}</summary>
+  </entry>
+"#,
+            name = escape_html(&recipe.name),
+            date = timestamp(&recipe.date),
+            description = escape_html(&recipe.description),
+        ));
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Where the canonical ingredient vocabulary lives, relative to the repo root.
+const VOCABULARY_PATH: &str = "docs/ingredients.json";
+
+/// Load the canonical vocabulary, or an empty one (with a warning) when it's missing.
+fn load_canonical(canonical_path: &std::path::Path) -> Result<CanonicalData> {
+    if canonical_path.exists() {
+        CanonicalData::load(canonical_path)
     } else {
         eprintln!("⚠️  WARNING: docs/ingredients.json not found; canonical validation disabled.");
-        CanonicalData::empty()
+        Ok(CanonicalData::empty())
+    }
+}
+
+/// Every recipe markdown file in `dir`, sorted by path.
+fn recipe_paths(dir: &std::path::Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory: {:?}", dir))? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("md") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Read and parse one recipe file, reusing the cached result when the source is unchanged.
+/// Read failures are reported as diagnostics. The flag says whether the cache was used.
+fn parse_path(
+    path: &std::path::Path,
+    lint: bool,
+    canonical: &CanonicalData,
+    cache: Option<&BuildCache>,
+) -> (Option<Recipe>, FileReport, bool) {
+    let mut diagnostics = Diagnostics::default();
+    let source = fs::read_to_string(path).unwrap_or_else(|e| {
+        diagnostics.error("read-error", None, format!("Failed to read file: {:?}: {}", path, e));
+        String::new()
+    });
+    let cached = match cache {
+        Some(cache) if !diagnostics.has_errors() => cache.get(path, &source),
+        _ => None,
+    };
+    let hit = cached.is_some();
+    let recipe = if hit {
+        cached
+    } else if diagnostics.has_errors() {
+        None
+    } else {
+        parse_recipe(&source, lint, canonical, &mut diagnostics)
     };
+    (recipe, FileReport { file: path.display().to_string(), source, diagnostics }, hit)
+}
 
-    let mut recipes = Vec::new();
-    let mut seen_ids = HashMap::new();
+/// Parse recipe files across all cores. Results come back in the order of `paths`, so
+/// output and diagnostics don't depend on thread scheduling.
+fn parse_all(
+    paths: &[PathBuf],
+    lint: bool,
+    canonical: &CanonicalData,
+    cache: Option<&BuildCache>,
+) -> Vec<(Option<Recipe>, FileReport, bool)> {
+    paths.par_iter().map(|path| parse_path(path, lint, canonical, cache)).collect()
+}
 
-    // Read all .md files in input directory
-    for entry in fs::read_dir(&cli.input)
-        .with_context(|| format!("Failed to read directory: {:?}", cli.input))?
-    {
-        let entry = entry?;
-        let path = entry.path();
+/// Write `contents` to `path` unless it already holds exactly that, so unchanged outputs keep
+/// their modification time. Returns whether the file was written.
+fn write_if_changed(path: &std::path::Path, contents: &str) -> Result<bool> {
+    if fs::read(path).is_ok_and(|existing| existing == contents.as_bytes()) {
+        return Ok(false);
+    }
+    fs::write(path, contents)?;
+    Ok(true)
+}
 
-        if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            println!("  📄 Parsing: {}", path.file_name().unwrap().to_string_lossy());
-
-            match parse_recipe_file(&path, cli.lint, &canonical) {
-                Ok(recipe) => {
-                    // Check for duplicate IDs
-                    if let Some(existing_path) = seen_ids.get(&recipe.id) {
-                        bail!("Duplicate recipe ID '{}' found in {:?} and {:?}",
-                              recipe.id, existing_path, path);
-                    }
-                    seen_ids.insert(recipe.id.clone(), path.clone());
-                    recipes.push(recipe);
-                }
-                Err(e) => {
-                    eprintln!("  ❌ Error in {:?}:\n  {}", path.file_name().unwrap_or_default(), e);
-                    if cli.lint {
-                        std::process::exit(1);
-                    }
-                }
-            }
+/// Sort by date descending (newest first), then by ID alphabetically for stable ordering.
+fn sort_newest_first(recipes: &mut [Recipe]) {
+    recipes.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.id.cmp(&b.id)));
+}
+
+/// Write the split layout next to `output`: `recipes/<id>.json` per recipe, removing files of
+/// deleted recipes, and `recipes-index.json` listing them newest first. Returns the manifest,
+/// whose version is the index hash, so it changes whenever any recipe does.
+fn write_split(recipes: &[Recipe], output: &std::path::Path) -> Result<Manifest> {
+    let dir = output.with_file_name("recipes");
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create recipe data directory: {:?}", dir))?;
+
+    let mut files = std::collections::BTreeMap::new();
+    let mut index = Vec::with_capacity(recipes.len());
+    let mut written = 0;
+    for recipe in recipes {
+        let json = serde_json::to_string_pretty(recipe)
+            .with_context(|| format!("Failed to serialize recipe: {}", recipe.id))?;
+        let path = dir.join(format!("{}.json", recipe.id));
+        if write_if_changed(&path, &json).with_context(|| format!("Failed to write recipe file: {:?}", path))? {
+            written += 1;
         }
+        let hash = sha256_hex(json.as_bytes());
+        files.insert(format!("recipes/{}.json", recipe.id), hash.clone());
+        index.push(IndexEntry {
+            id: &recipe.id,
+            name: &recipe.name,
+            time: recipe.time,
+            difficulty: &recipe.difficulty,
+            diet: &recipe.diet,
+            cuisine: &recipe.cuisine,
+            meal_type: &recipe.meal_type,
+            tested: recipe.tested,
+            hash,
+        });
     }
 
-    if recipes.is_empty() {
-        bail!("No valid recipes found in {:?}", cli.input);
+    // Remove files of deleted recipes
+    let mut removed = 0;
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read recipe data directory: {:?}", dir))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.ends_with(".json") && !files.contains_key(&format!("recipes/{}", name)) {
+            fs::remove_file(entry.path())
+                .with_context(|| format!("Failed to remove stale recipe file: {:?}", entry.path()))?;
+            removed += 1;
+        }
+    }
+    if removed > 0 {
+        status!("🗑️  Removed {} stale recipe file(s) from {:?}", removed, dir);
     }
+    status!("📝 Written {} recipe file(s) to: {:?} ({} changed)", recipes.len(), dir, written);
 
-    // Sort by date descending (newest first), then by ID alphabetically for stable ordering
-    recipes.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.id.cmp(&b.id)));
+    let index_path = output.with_file_name("recipes-index.json");
+    let index_json = serde_json::to_string_pretty(&index).context("Failed to serialize recipe index")?;
+    write_if_changed(&index_path, &index_json)
+        .with_context(|| format!("Failed to write recipe index: {:?}", index_path))?;
+    status!("📇 Index written to: {:?}", index_path);
 
-    println!("\n✅ Successfully parsed {} recipe(s)", recipes.len());
+    let version = sha256_hex(index_json.as_bytes());
+    files.insert("recipes-index.json".to_string(), version.clone());
+    Ok(Manifest {
+        version,
+        recipe_count: recipes.len(),
+        schema_version: schema::SCHEMA_VERSION,
+        files: Some(files),
+        deltas: Vec::new(),
+    })
+}
 
-    // Write JSON output
-    let json = serde_json::to_string_pretty(&recipes)
-        .context("Failed to serialize recipes to JSON")?;
+/// Write the patches from the previous recipes.json (and those the previous manifest lists) to
+/// `json`, returning the old versions that now have one.
+fn write_deltas(output: &std::path::Path, manifest_path: &std::path::Path, json: &str, recipes: &[Recipe]) -> Result<Vec<String>> {
+    let previous_manifest = fs::read_to_string(manifest_path).ok()
+        .and_then(|contents| serde_json::from_str::<Manifest>(&contents).ok());
+    // Clients of an older schema download everything, so don't patch across a schema change
+    let same_schema = previous_manifest.as_ref().is_none_or(|m| m.schema_version == schema::SCHEMA_VERSION);
+    let previous = fs::read_to_string(output).ok().filter(|_| same_schema);
+    let listed = previous_manifest.filter(|_| same_schema).map(|manifest| manifest.deltas).unwrap_or_default();
+
+    let deltas_dir = output.with_file_name("deltas");
+    let deltas = delta::update(&deltas_dir, previous.as_deref(), json, recipes, &listed)?;
+    status!("🩹 {} delta(s) to this version in: {:?}", deltas.len(), deltas_dir);
+    Ok(deltas)
+}
 
-    fs::write(&cli.output, &json)
-        .with_context(|| format!("Failed to write output file: {:?}", cli.output))?;
+/// Write `output` (recipes.json, or the split layout in its place), the manifest, search index,
+/// similar recipes, sitemap and feed next to it and the static recipe pages. `imperial` adds
+/// the US-customary alternatives first; `deltas` patches the previous recipes.json to this one,
+/// which only a real build should do, not watch and serve rebuilding on every save.
+fn write_outputs(mut recipes: Vec<Recipe>, output: &std::path::Path, layout: Layout, imperial: bool, deltas: bool, canonical: &CanonicalData) -> Result<()> {
+    sort_newest_first(&mut recipes);
+    if imperial {
+        let skipped = imperial::annotate(&mut recipes, canonical);
+        if !skipped.is_empty() {
+            status!("⚖️  No density for {} ingredient(s), so their weights stay in oz/lb rather than cups: {}", skipped.len(), skipped.join(", "));
+        }
+    }
+    let manifest_path = output.with_file_name("recipes-manifest.json");
 
-    println!("📝 Written to: {:?}", cli.output);
+    let manifest = match layout {
+        Layout::Single => {
+            // Write JSON output
+            let json = serde_json::to_string_pretty(&recipes)
+                .context("Failed to serialize recipes to JSON")?;
+            // Deltas first: if they fail, recipes.json still holds the build they patch from
+            let deltas = if deltas { write_deltas(output, &manifest_path, &json, &recipes)? } else { Vec::new() };
 
-    // Generate manifest with hash of recipes.json
-    let mut hasher = Sha256::new();
-    hasher.update(json.as_bytes());
-    let hash = format!("{:x}", hasher.finalize());
+            write_if_changed(output, &json)
+                .with_context(|| format!("Failed to write output file: {:?}", output))?;
 
-    let manifest = Manifest {
-        version: hash,
-        recipe_count: recipes.len(),
+            status!("📝 Written to: {:?}", output);
+
+            // The manifest version is the hash of recipes.json
+            Manifest {
+                version: sha256_hex(json.as_bytes()),
+                recipe_count: recipes.len(),
+                schema_version: schema::SCHEMA_VERSION,
+                files: None,
+                deltas,
+            }
+        }
+        Layout::Split => write_split(&recipes, output)?,
     };
 
     // Write manifest.json next to recipes.json
-    let manifest_path = cli.output.with_file_name("recipes-manifest.json");
     let manifest_json = serde_json::to_string_pretty(&manifest)
         .context("Failed to serialize manifest")?;
 
-    fs::write(&manifest_path, manifest_json)
+    write_if_changed(&manifest_path, &manifest_json)
         .with_context(|| format!("Failed to write manifest file: {:?}", manifest_path))?;
 
-    println!("📦 Manifest written to: {:?}", manifest_path);
-
-    // Generate per-recipe OG HTML files
-    let og_dir = cli.output.parent().unwrap_or_else(|| std::path::Path::new(".")).join("r");
-    fs::create_dir_all(&og_dir)
-        .with_context(|| format!("Failed to create OG directory: {:?}", og_dir))?;
+    status!("📦 Manifest written to: {:?}", manifest_path);
+
+    let index_path = output.with_file_name("search-index.json");
+    let index_json = serde_json::to_string(&search::SearchIndex::build(&recipes, canonical))
+        .context("Failed to serialize search index")?;
+    write_if_changed(&index_path, &index_json)
+        .with_context(|| format!("Failed to write search index: {:?}", index_path))?;
+    status!("🔎 Search index written to: {:?}", index_path);
+
+    let similar_path = output.with_file_name("similar-recipes.json");
+    let similar_json = serde_json::to_string_pretty(&similar::compute(&recipes, canonical))
+        .context("Failed to serialize similar recipes")?;
+    write_if_changed(&similar_path, &similar_json)
+        .with_context(|| format!("Failed to write similar recipes: {:?}", similar_path))?;
+    status!("🧭 Similar recipes written to: {:?}", similar_path);
+
+    // Sitemap and feed live next to the manifest, and are rewritten only when they change
+    let sitemap_path = output.with_file_name("sitemap.xml");
+    write_if_changed(&sitemap_path, &generate_sitemap(&recipes))
+        .with_context(|| format!("Failed to write sitemap: {:?}", sitemap_path))?;
+    let feed_path = output.with_file_name("feed.xml");
+    write_if_changed(&feed_path, &generate_feed(&recipes))
+        .with_context(|| format!("Failed to write feed: {:?}", feed_path))?;
+    status!("🗺️  Sitemap and feed written to: {:?}, {:?}", sitemap_path, feed_path);
+
+    // Generate the static recipe pages
+    let pages_dir = output.parent().unwrap_or_else(|| std::path::Path::new(".")).join("r");
+    fs::create_dir_all(&pages_dir)
+        .with_context(|| format!("Failed to create recipe page directory: {:?}", pages_dir))?;
 
     let expected: std::collections::HashSet<String> = recipes.iter()
         .map(|r| format!("{}.html", r.id))
         .collect();
 
+    let mut written = 0;
     for recipe in &recipes {
-        let og_path = og_dir.join(format!("{}.html", recipe.id));
-        fs::write(&og_path, generate_og_html(recipe))
-            .with_context(|| format!("Failed to write OG file: {:?}", og_path))?;
+        let page_path = pages_dir.join(format!("{}.html", recipe.id));
+        if write_if_changed(&page_path, &generate_recipe_html(recipe))
+            .with_context(|| format!("Failed to write recipe page: {:?}", page_path))?
+        {
+            written += 1;
+        }
     }
 
-    // Remove stale OG files for deleted recipes
+    // Remove stale pages for deleted recipes
     let mut removed = 0;
-    for entry in fs::read_dir(&og_dir).with_context(|| format!("Failed to read OG directory: {:?}", og_dir))? {
+    for entry in fs::read_dir(&pages_dir).with_context(|| format!("Failed to read recipe page directory: {:?}", pages_dir))? {
         let entry = entry?;
         let name = entry.file_name();
         let name_str = name.to_string_lossy();
         if name_str.ends_with(".html") && !expected.contains(name_str.as_ref()) {
             fs::remove_file(entry.path())
-                .with_context(|| format!("Failed to remove stale OG file: {:?}", entry.path()))?;
+                .with_context(|| format!("Failed to remove stale recipe page: {:?}", entry.path()))?;
             removed += 1;
         }
     }
 
     if removed > 0 {
-        println!("🗑️  Removed {} stale OG HTML file(s) from {:?}", removed, og_dir);
+        status!("🗑️  Removed {} stale recipe page(s) from {:?}", removed, pages_dir);
+    }
+    status!("🔗 Generated {} recipe page(s) in {:?} ({} changed)", recipes.len(), pages_dir, written);
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    // Progress output would corrupt the machine-readable formats on stdout
+    QUIET.store(cli.format != Format::Text, Ordering::Relaxed);
+
+    if let Some(Command::Fmt { check, files }) = &cli.command {
+        let files = if files.is_empty() { recipe_paths(&cli.input)? } else { files.clone() };
+        if !fmt::run(&files, *check, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::New(args)) = cli.command {
+        return new::run(args, &cli.input, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?);
+    }
+
+    if let Some(Command::Import { from, file, id }) = cli.command {
+        return import::run(from, &file, id, &cli.input, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?);
+    }
+
+    if let Some(Command::Convert { to, from: _, file, output }) = &cli.command {
+        return cooklang::run(*to, file, output.as_deref(), &cli.input, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?);
+    }
+
+    if let Some(Command::Schema) = &cli.command {
+        return schema::run();
+    }
+
+    if let Some(Command::Plan(args)) = &cli.command {
+        return plan::run(args, &cli.input, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?);
+    }
+
+    if let Some(Command::Shop { recipes, json }) = &cli.command {
+        return shopping::run(recipes, *json, &cli.input, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?);
+    }
+
+    if let Some(Command::Search { query, limit }) = &cli.command {
+        let index = cli.output.with_file_name("search-index.json");
+        return search::run(&index, query, *limit, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?);
+    }
+
+    if let Some(Command::Nutrition { min_coverage }) = &cli.command {
+        return nutrition::run(&cli.input, *min_coverage, &load_canonical(std::path::Path::new(VOCABULARY_PATH))?);
+    }
+
+    if let Some(Command::VerifyDelta { old, delta }) = &cli.command {
+        return delta::run(old, delta);
+    }
+
+    if let Some(Command::Watch) = &cli.command {
+        return watch::run(&cli.input, &cli.output, std::path::Path::new(VOCABULARY_PATH), cli.lint, cli.layout, cli.imperial);
+    }
+
+    if let Some(Command::Serve { port }) = &cli.command {
+        return serve::run(&cli.input, &cli.output, std::path::Path::new(VOCABULARY_PATH), cli.lint, cli.layout, cli.imperial, *port);
+    }
+
+    status!("🔍 Parsing recipes from: {:?}", cli.input);
+
+    if !cli.input.exists() {
+        bail!("Input directory does not exist: {:?}", cli.input);
+    }
+
+    let canonical = load_canonical(std::path::Path::new(VOCABULARY_PATH))?;
+    let mut cache = (!cli.no_cache).then(|| BuildCache::load(&cli.cache, std::path::Path::new(VOCABULARY_PATH), cli.lint));
+
+    let paths = recipe_paths(&cli.input)?;
+
+    if cli.fix {
+        let mut fixes = 0;
+        for path in &paths {
+            let Ok(source) = fs::read_to_string(path) else { continue };
+            let (fixed, applied) = fix::fix_source(&source, &canonical);
+            fixes += applied.len();
+            if fixed == source {
+                continue;
+            }
+            if cli.dry_run {
+                print!("{}", fix::unified_diff(&path.display().to_string(), &source, &fixed));
+                continue;
+            }
+            fs::write(path, &fixed)
+                .with_context(|| format!("Failed to write fixed recipe: {:?}", path))?;
+            status!("  🔧 Fixed: {}", path.file_name().unwrap().to_string_lossy());
+            for applied_fix in &applied {
+                status!("    🔧 line {}: [{}] {}", applied_fix.line, applied_fix.rule, applied_fix.label);
+            }
+        }
+        if cli.dry_run {
+            eprintln!("🔧 {} fix(es) would be applied (dry run, nothing written)", fixes);
+            return Ok(());
+        }
+        status!("🔧 Applied {} fix(es)", fixes);
+    }
+
+    let mut recipes = Vec::new();
+    let mut seen_ids = HashMap::new();
+    let mut reports = Vec::new();
+    let mut duplicate_ids = false;
+
+    // Parsing runs in parallel; everything order-sensitive happens here, in path order
+    for (path, (recipe, mut report, cached)) in paths.iter().zip(parse_all(&paths, cli.lint, &canonical, cache.as_ref())) {
+        status!("  📄 Parsing: {}", path.file_name().unwrap().to_string_lossy());
+
+        if let (Some(cache), Some(recipe)) = (cache.as_mut(), &recipe) {
+            if cached {
+                cache.keep(path);
+            } else if report.diagnostics.is_empty() {
+                // Anything with diagnostics is re-parsed every run so they keep being reported
+                cache.insert(path, &report.source, recipe);
+            }
+        }
+
+        if let Some(recipe) = recipe {
+            // Check for duplicate IDs
+            if let Some(existing_path) = seen_ids.get(&recipe.id) {
+                report.diagnostics.error("duplicate-id", None, format!(
+                    "Duplicate recipe ID '{}' found in {:?} and {:?}",
+                    recipe.id, existing_path, path
+                ));
+                duplicate_ids = true;
+            } else {
+                seen_ids.insert(recipe.id.clone(), path.clone());
+                recipes.push(recipe);
+            }
+        }
+        reports.push(report);
+    }
+
+    if let Some(cache) = &cache {
+        if cache.hits > 0 {
+            status!("♻️  Reused {} unchanged recipe(s) from the build cache", cache.hits);
+        }
+        if let Err(e) = cache.save() {
+            eprintln!("⚠️  WARNING: {:#}", e);
+        }
+    }
+
+    diagnostics::print_reports(&reports, cli.format);
+    let has_errors = reports.iter().any(|r| r.diagnostics.has_errors());
+    if (cli.lint && has_errors) || duplicate_ids {
+        std::process::exit(1);
+    }
+
+    if recipes.is_empty() {
+        bail!("No valid recipes found in {:?}", cli.input);
     }
-    println!("🔗 Generated {} OG HTML file(s) in {:?}", recipes.len(), og_dir);
+
+    status!("\n✅ Successfully parsed {} recipe(s)", recipes.len());
+    write_outputs(recipes, &cli.output, cli.layout, cli.imperial, true, &canonical)?;
 
     if cli.lint {
-        println!("🔬 Linting passed!");
+        status!("🔬 Linting passed!");
     }
 
     Ok(())
@@ -1384,8 +2481,26 @@ fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use diagnostics::Severity;
+    use fixtures::TestRecipe;
     use std::fs;
 
+    /// Parse a recipe file, folding all reported errors into a single `Err`.
+    fn parse_recipe_file(path: &PathBuf, lint: bool, canonical: &CanonicalData) -> Result<Recipe> {
+        let content = fs::read_to_string(path)?;
+        let mut diagnostics = Diagnostics::default();
+        match parse_recipe(&content, lint, canonical, &mut diagnostics) {
+            Some(recipe) => Ok(recipe),
+            None => {
+                let messages: Vec<&str> = diagnostics.iter()
+                    .filter(|d| d.severity == Severity::Error)
+                    .map(|d| d.message.as_str())
+                    .collect();
+                bail!("{}", messages.join("\n"))
+            }
+        }
+    }
+
     #[test]
     fn test_valid_recipe_parsing() {
         let test_recipe = r#"---
@@ -1532,6 +2647,99 @@ date: 2026-01-01
         assert!(result.unwrap_err().to_string().contains("Description too short"));
     }
 
+    #[test]
+    fn test_lint_collects_every_error() {
+        let test_recipe = r#"---
+id: many-errors
+name: Many Errors
+description: Recipe with several independent problems
+servings: 2
+time: 15
+difficulty: super-hard
+diet: [keto]
+cuisine: [french]
+meal_type: [dinner]
+date: 2026-01-01
+---
+
+# Ingredients
+
+- 500g [garlic]
+- 1 tbsp olive oil
+- ½ tsp [salt]
+
+# Instructions
+
+1. Use {garlic} and {salt}
+"#;
+
+        let mut diagnostics = Diagnostics::default();
+        let result = parse_recipe(test_recipe, true, &make_canonical_data(), &mut diagnostics);
+
+        assert!(result.is_none());
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(diagnostics.error_count(), 5, "got: {:#?}", messages);
+        assert!(messages.iter().any(|m| m.contains("Difficulty 'super-hard'")));
+        assert!(messages.iter().any(|m| m.contains("Invalid diet value")));
+        assert!(messages.iter().any(|m| m.contains("'500g' should be '500 g'")));
+        assert!(messages.iter().any(|m| m.contains("missing a [canonical] tag")));
+        assert!(messages.iter().any(|m| m.contains("Unicode fraction")));
+        // The untagged oil is never referenced in a step
+        assert_eq!(diagnostics.warning_count(), 1);
+    }
+
+    #[test]
+    fn test_diagnostics_point_at_source_lines() {
+        let test_recipe = r#"---
+id: spans-test
+name: Spans Test
+description: Diagnostics should carry source locations
+servings: 2
+time: 15
+difficulty: easy
+diet: [vegan, keto]
+cuisine: [french]
+meal_type: [dinner]
+date: 2026-01-01
+---
+
+# Ingredients
+
+- 2 cloves [garlic]
+- 1 tbsp olive oil
+
+# Instructions
+
+1. Fry {garlic}
+"#;
+
+        let mut diagnostics = Diagnostics::default();
+        parse_recipe(test_recipe, true, &make_canonical_data(), &mut diagnostics);
+
+        let located: Vec<(usize, usize, &str)> = diagnostics.iter()
+            .map(|d| {
+                let span = d.span.clone().expect("diagnostic should be located");
+                let (line, column) = diagnostics::line_col(test_recipe, span.start);
+                (line, column, &test_recipe[span])
+            })
+            .collect();
+        assert_eq!(located, vec![
+            (8, 15, "keto"),
+            (17, 3, "1 tbsp olive oil"),
+            (17, 3, "1 tbsp olive oil"),
+        ]);
+    }
+
+    #[test]
+    fn test_yaml_error_points_at_broken_line() {
+        let test_recipe = "---\nid: yaml-test\nname: Yaml Test\ntime 15\nservings: 2\n---\n";
+        let mut diagnostics = Diagnostics::default();
+        assert!(parse_recipe(test_recipe, false, &CanonicalData::empty(), &mut diagnostics).is_none());
+        let span = diagnostics.iter().next().unwrap().span.clone().unwrap();
+        assert_eq!(diagnostics::line_col(test_recipe, span.start), (4, 1));
+        assert_eq!(&test_recipe[span], "time 15");
+    }
+
     #[test]
     fn test_optional_sections() {
         let test_recipe = r#"---
@@ -2689,6 +3897,8 @@ date: 2026-01-01
         assert_eq!(gq.amount, 3.0);
         assert_eq!(gq.amount_max, Some(4.0));
         assert_eq!(gq.unit.as_deref(), Some("cloves"));
+        assert_eq!(gq.canonical_unit.as_deref(), Some("clove"));
+        assert_eq!(gq.dimension, Some(Dimension::Count));
 
         // Find lemon by text content
         let lemon = pantry.iter().find(|i| i.text.contains("lemon")).unwrap();
@@ -2754,7 +3964,8 @@ date: 2026-01-01
             Ingredient { id: 3, text: "2 tbsp olive oil".to_string(), canonical: None, preparation: None, quantity: None },
         ]);
         let refs = vec!["ice cubes".to_string(), "lemon".to_string()];
-        let unreferenced = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty());
+        let unreferenced: Vec<String> = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty())
+            .iter().map(|(category, ing)| format!("{} ({})", ing.text, category)).collect();
         assert_eq!(unreferenced, vec!["2 tbsp olive oil (Fresh)"]);
     }
 
@@ -2766,7 +3977,8 @@ date: 2026-01-01
             Ingredient { id: 2, text: "120 g tahini".to_string(), canonical: None, preparation: None, quantity: None },
         ]);
         let refs = vec!["chickpeas".to_string(), "tahini".to_string()];
-        let unreferenced = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty());
+        let unreferenced: Vec<String> = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty())
+            .iter().map(|(category, ing)| format!("{} ({})", ing.text, category)).collect();
         assert!(unreferenced.is_empty());
     }
 
@@ -2777,7 +3989,8 @@ date: 2026-01-01
             Ingredient { id: 1, text: "Salt to taste".to_string(), canonical: None, preparation: None, quantity: None },
         ]);
         let refs = vec!["oil".to_string()];
-        let unreferenced = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty());
+        let unreferenced: Vec<String> = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty())
+            .iter().map(|(category, ing)| format!("{} ({})", ing.text, category)).collect();
         assert_eq!(unreferenced, vec!["Salt to taste (Spices)"]);
     }
 
@@ -2964,9 +4177,11 @@ date: 2026-01-01
             ingredients.insert(key.to_string(), key.to_string());
         }
         // plural entries
-        ingredients.insert("eggs".to_string(), "egg".to_string());
-        ingredients.insert("chickpeas".to_string(), "chickpea".to_string());
-        ingredients.insert("mushrooms".to_string(), "mushroom".to_string());
+        let mut ingredient_plurals = HashMap::new();
+        for (singular, plural) in [("egg", "eggs"), ("chickpea", "chickpeas"), ("mushroom", "mushrooms")] {
+            ingredients.insert(plural.to_string(), singular.to_string());
+            ingredient_plurals.insert(singular.to_string(), plural.to_string());
+        }
         // sections
         ingredient_sections.insert("garlic".to_string(), "Fresh".to_string());
         ingredient_sections.insert("mushroom".to_string(), "Fresh".to_string());
@@ -2979,7 +4194,25 @@ date: 2026-01-01
             "tbsp".to_string(), "tsp".to_string(),
             "g".to_string(), "ml".to_string(),
         ];
-        CanonicalData { ingredients, ingredient_sections, units }
+        let unit_plurals = HashMap::from([("clove".to_string(), "cloves".to_string())]);
+        let unit_dimensions = HashMap::from([
+            ("clove".to_string(), (Dimension::Count, 1.0)),
+            ("tbsp".to_string(), (Dimension::Volume, 15.0)),
+            ("tsp".to_string(), (Dimension::Volume, 5.0)),
+            ("g".to_string(), (Dimension::Mass, 1.0)),
+            ("ml".to_string(), (Dimension::Volume, 1.0)),
+        ]);
+        CanonicalData {
+            ingredients,
+            ingredient_sections,
+            ingredient_plurals,
+            units,
+            unit_plurals,
+            unit_dimensions,
+            ingredient_densities: HashMap::new(),
+            ingredient_nutrition: HashMap::new(),
+            ingredient_grams: HashMap::new(),
+        }
     }
 
     #[test]
@@ -3036,6 +4269,22 @@ date: 2026-01-01
         assert!(salt.quantity.is_none());
     }
 
+    #[test]
+    fn test_unit_conversion() {
+        let canonical = CanonicalData::empty();
+        assert_eq!(canonical.convert_unit(1.0, "l", "ml"), Some(1000.0));
+        assert_eq!(canonical.convert_unit(1.0, "tbsp", "tsp"), Some(3.0));
+        assert_eq!(canonical.convert_unit(2.0, "cups", "cup"), Some(2.0));
+        assert_eq!(canonical.convert_unit(1.0, "g", "ml"), None);
+        assert_eq!(canonical.convert_unit(1.0, "clove", "tin"), None);
+
+        let path = std::env::temp_dir().join(format!("recipe-parser-units-{}.json", std::process::id()));
+        fs::write(&path, r#"{"ingredients": {}, "units": {"pinch": {"plural": "pinches", "dimension": "count"}, "g": {"dimension": "mass"}}}"#).unwrap();
+        let err = CanonicalData::load(&path).err().unwrap();
+        fs::remove_file(&path).ok();
+        assert!(err.to_string().contains("'g' needs a positive factor"));
+    }
+
     #[test]
     fn test_plural_normalises_canonical_keeps_item() {
         // [eggs] → canonical "egg" (singular), quantity.item "eggs" (as written)
@@ -3207,7 +4456,8 @@ date: 2026-01-01
 
         // {oil} — does not match either canonical exactly
         let refs = vec!["oil".to_string()];
-        let unreferenced = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty());
+        let unreferenced: Vec<String> = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty())
+            .iter().map(|(category, ing)| format!("{} ({})", ing.text, category)).collect();
         assert_eq!(unreferenced.len(), 2); // both are unreferenced
     }
 
@@ -3221,7 +4471,8 @@ date: 2026-01-01
         ]);
 
         let refs = vec!["egg".to_string()];
-        let unreferenced = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty());
+        let unreferenced: Vec<String> = find_unreferenced_ingredients(&ingredients, &refs, &CanonicalData::empty())
+            .iter().map(|(category, ing)| format!("{} ({})", ing.text, category)).collect();
         assert!(unreferenced.is_empty()); // {egg} matches canonical "egg"
     }
 
@@ -3307,4 +4558,241 @@ date: 2026-01-01
         let d = parse_step_durations("stir until golden");
         assert!(d.is_empty());
     }
+
+    #[test]
+    fn test_parse_all_matches_serial_parse() {
+        let dir = std::env::temp_dir().join(format!("recipe-parse-all-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..40u8 {
+            // Every fifth recipe has a lint error, so reports differ from file to file
+            let quantity = if i % 5 == 0 { "500g" } else { "500 g" };
+            let letter = |n: u8| (b'a' + n) as char;
+            let id = format!("recipe-{}{}", letter(i / 26), letter(i % 26));
+            fs::write(dir.join(format!("recipe-{:02}.md", i)), format!(r#"---
+id: {id}
+name: Recipe {i}
+description: A recipe used to exercise parallel parsing
+servings: 2
+time: {time}
+difficulty: easy
+diet: [vegan]
+cuisine: [british]
+meal_type: [dinner]
+date: 2025-01-{day:02}
+---
+
+# Ingredients
+
+- {quantity} [flour]
+
+# Instructions
+
+1. Bake the {{flour}} for ten minutes.
+"#, id = id, i = i, time = 10 + i, day = 1 + i % 28, quantity = quantity)).unwrap();
+        }
+
+        let paths = recipe_paths(&dir).unwrap();
+        let canonical = CanonicalData::empty();
+        let summarise = |results: Vec<(Option<Recipe>, FileReport, bool)>| -> Vec<(String, String, usize)> {
+            results.into_iter().map(|(recipe, report, _)| (
+                serde_json::to_string(&recipe).unwrap(),
+                report.file,
+                report.diagnostics.error_count(),
+            )).collect()
+        };
+        let serial = summarise(paths.iter().map(|p| parse_path(p, true, &canonical, None)).collect());
+        let parallel = summarise(parse_all(&paths, true, &canonical, None));
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel.iter().filter(|(_, _, errors)| *errors > 0).count(), 8);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recipe_html_contains_recipe_json_ld() {
+        let content = r#"---
+id: json-ld-recipe
+name: Spiced </script> Lentils
+description: A recipe used to exercise the structured data
+servings: 4
+time: 95
+difficulty: easy
+diet: [vegan, gluten-free]
+cuisine: [indian]
+meal_type: [dinner]
+date: 2025-01-01
+---
+
+# Ingredients
+
+- 250 g [lentils]
+- 1 tsp [turmeric]
+
+- 1 [onion], diced
+
+# Instructions
+
+1. Fry the {onion}.
+2. Add the {lentils} and {turmeric} and simmer for 20 minutes.
+"#;
+        let recipe = parse_recipe(content, false, &CanonicalData::empty(), &mut Diagnostics::default()).unwrap();
+        let html = generate_recipe_html(&recipe);
+        assert!(!html.contains("Spiced </script>"));
+
+        let start = html.find(r#"<script type="application/ld+json">"#).unwrap();
+        let end = start + html[start..].find("</script>").unwrap();
+        let json = &html[start + html[start..].find('>').unwrap() + 1..end];
+        let data: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        // Properties Google requires or recommends for recipe rich results
+        for property in ["name", "image", "description", "author", "datePublished", "totalTime", "recipeYield",
+            "recipeCuisine", "recipeCategory", "recipeIngredient", "recipeInstructions"] {
+            assert!(!data[property].is_null(), "missing {}", property);
+        }
+        assert_eq!(data["@context"], "https://schema.org");
+        assert_eq!(data["@type"], "Recipe");
+        assert_eq!(data["name"], "Spiced </script> Lentils");
+        assert_eq!(data["totalTime"], "PT1H35M");
+        assert_eq!(data["recipeYield"], "4 servings");
+        assert_eq!(data["suitableForDiet"], serde_json::json!(["https://schema.org/VeganDiet", "https://schema.org/GlutenFreeDiet"]));
+        assert_eq!(data["recipeIngredient"], serde_json::json!(["250 g lentils", "1 tsp turmeric", "1 onion, diced"]));
+        assert_eq!(data["recipeInstructions"][1], serde_json::json!({
+            "@type": "HowToStep",
+            "position": 2,
+            "text": "Add the lentils and turmeric and simmer for 20 minutes.",
+        }));
+    }
+
+    #[test]
+    fn test_recipe_html_renders_full_recipe() {
+        let content = r#"---
+id: static-page
+name: Garlic & Salt
+description: A recipe used to exercise the static page
+servings: 2
+time: 10
+difficulty: easy
+diet: [vegan]
+cuisine: [french]
+meal_type: [dinner]
+date: 2025-01-01
+---
+
+# Notes
+
+Use "flaky" salt & oil.
+
+Second paragraph.
+
+# Ingredients
+
+- 1 pinch [salt]
+- 2 cloves [garlic], minced
+- 1 tbsp [olive oil]
+
+# Instructions
+
+1. Warm the {olive oil} and fry the {garlic}.
+2. Season with {salt}.
+
+# Serving Suggestions
+
+With bread.
+"#;
+        let recipe = parse_recipe(content, false, &make_canonical_data(), &mut Diagnostics::default()).unwrap();
+        let html = generate_recipe_html(&recipe);
+
+        assert!(html.contains(r#"<meta property="og:title" content="Garlic &amp; Salt — BiteMe">"#));
+        assert!(html.contains(r#"<a href="/recipe.html?id=static-page" class="button button-primary">Open in BiteMe</a>"#));
+        assert!(!html.contains("window.location"));
+        assert!(html.contains("<p class=\"cooking-stats\">2 servings · 10 min · easy</p>"));
+
+        // Sections in VALID_CATEGORIES order, not ingredient order
+        let fresh = html.find("<h4>Fresh</h4>").unwrap();
+        let condiments = html.find("<h4>Condiments</h4>").unwrap();
+        let spices = html.find("<h4>Spices</h4>").unwrap();
+        assert!(fresh < condiments && condiments < spices);
+        assert!(html.contains("<li>2 cloves garlic, minced</li>"));
+
+        assert!(html.contains("<li>Warm the olive oil and fry the garlic.</li>\n          <li>Season with salt.</li>"));
+        assert!(html.contains("<h3>Notes</h3>\n        <p>Use &quot;flaky&quot; salt &amp; oil.</p>\n        <p>Second paragraph.</p>"));
+        assert!(html.contains("<h3>Serving Suggestions</h3>\n        <p>With bread.</p>"));
+    }
+
+    #[test]
+    fn test_sitemap_and_feed() {
+        let recipes: Vec<Recipe> = (0..FEED_ENTRIES + 2).map(|i| {
+            let id = format!("recipe-{}", "abcdefghijklmnopqrstuvwxyz".chars().nth(i).unwrap());
+            TestRecipe::new(&id).set("name", format!("Recipe & {}", i)).set("date", format!("2025-01-{:02}", 28 - i))
+                .parse(&CanonicalData::empty())
+        }).collect();
+
+        let sitemap = generate_sitemap(&recipes);
+        assert!(sitemap.contains("<loc>https://biteme.ovh/</loc>\n    <lastmod>2025-01-28</lastmod>"));
+        assert!(sitemap.contains("<loc>https://biteme.ovh/r/recipe-b.html</loc>\n    <lastmod>2025-01-27</lastmod>"));
+        assert_eq!(sitemap.matches("<url>").count(), recipes.len() + 1);
+
+        let feed = generate_feed(&recipes);
+        assert!(feed.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+        assert!(feed.contains("<updated>2025-01-28T00:00:00Z</updated>\n  <author>"));
+        assert!(feed.contains("<title>Recipe &amp; 0</title>\n    <link href=\"https://biteme.ovh/r/recipe-a.html\"/>"));
+        assert!(feed.contains("<summary>A recipe used in tests</summary>"));
+        assert_eq!(feed.matches("<entry>").count(), FEED_ENTRIES);
+        assert!(!feed.contains("recipe-v.html"));
+    }
+
+    #[test]
+    fn test_split_layout() {
+        let recipe = |id: &str, date: &str| {
+            TestRecipe::new(id).set("date", date).steps("1. Chop the {onion}.").parse(&CanonicalData::empty())
+        };
+        let dir = std::env::temp_dir().join(format!("recipe-parser-split-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("recipes")).unwrap();
+        fs::write(dir.join("recipes/deleted.json"), "{}").unwrap();
+        let output = dir.join("recipes.json");
+        let read_json = |name: &str| -> serde_json::Value {
+            serde_json::from_str(&fs::read_to_string(dir.join(name)).unwrap()).unwrap()
+        };
+
+        write_outputs(vec![recipe("older", "2025-01-01"), recipe("newer", "2025-02-01")], &output, Layout::Split, false, true, &CanonicalData::empty()).unwrap();
+
+        assert!(!output.exists());
+        assert!(!dir.join("recipes/deleted.json").exists());
+        let file = fs::read_to_string(dir.join("recipes/newer.json")).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&file).unwrap()["steps"][0]["text"], "Chop the {onion}.");
+
+        let index = read_json("recipes-index.json");
+        assert_eq!(index[0]["id"], "newer");
+        assert_eq!(index[1]["id"], "older");
+        assert_eq!(index[0]["hash"], sha256_hex(file.as_bytes()));
+        assert!(index[0].get("steps").is_none());
+
+        let manifest = read_json("recipes-manifest.json");
+        assert_eq!(manifest["recipe_count"], 2);
+        assert_eq!(manifest["files"]["recipes/newer.json"], sha256_hex(file.as_bytes()));
+        assert_eq!(manifest["version"], sha256_hex(&fs::read(dir.join("recipes-index.json")).unwrap()));
+        assert_eq!(manifest["files"]["recipes-index.json"], manifest["version"]);
+        assert!(dir.join("r/older.html").exists());
+
+        // Changing one recipe changes only its own hash
+        let mut changed = recipe("older", "2025-01-01");
+        changed.time = 20;
+        write_outputs(vec![changed, recipe("newer", "2025-02-01")], &output, Layout::Split, false, true, &CanonicalData::empty()).unwrap();
+        let updated = read_json("recipes-manifest.json");
+        assert_eq!(updated["files"]["recipes/newer.json"], manifest["files"]["recipes/newer.json"]);
+        assert_ne!(updated["files"]["recipes/older.json"], manifest["files"]["recipes/older.json"]);
+        assert_ne!(updated["version"], manifest["version"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_iso_duration_and_step_refs() {
+        assert_eq!(iso_duration(45), "PT45M");
+        assert_eq!(iso_duration(120), "PT2H");
+        assert_eq!(iso_duration(90), "PT1H30M");
+        assert_eq!(strip_step_refs("Add {garlic} and {olive oil} {"), "Add garlic and olive oil {");
+    }
 }
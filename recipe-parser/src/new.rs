@@ -0,0 +1,439 @@
+//! `new`: scaffold `recipes/<id>.md` from a recipe name, with frontmatter that already passes
+//! validation and the standard sections left to fill in. Values not given as flags are asked
+//! for interactively, offering only the allowed ones.
+
+use crate::diagnostics::Diagnostics;
+use crate::{parse_recipe, recipe_paths, CanonicalData, VALID_CUISINES, VALID_DIETS, VALID_DIFFICULTIES, VALID_MEAL_TYPES};
+use anyhow::{bail, Context, Result};
+use clap::builder::PossibleValuesParser;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Recipes can't be built until these are filled in, which is the point of the skeleton.
+const SKELETON_ERRORS: [&str; 2] = ["missing-ingredients", "missing-steps"];
+
+#[derive(clap::Args)]
+pub(crate) struct NewArgs {
+    /// Recipe name, e.g. "Thai Green Curry"; the id and file name are derived from it
+    name: String,
+
+    /// Use this id instead of the one derived from the name
+    #[arg(long)]
+    id: Option<String>,
+
+    /// One-line description shown on the recipe card
+    #[arg(long)]
+    description: Option<String>,
+
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=100))]
+    servings: Option<u32>,
+
+    /// Total time in minutes
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=1440))]
+    time: Option<u32>,
+
+    #[arg(long, value_parser = PossibleValuesParser::new(VALID_DIFFICULTIES))]
+    difficulty: Option<String>,
+
+    /// Comma-separated
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(VALID_DIETS))]
+    diet: Vec<String>,
+
+    /// Comma-separated
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(VALID_CUISINES))]
+    cuisine: Vec<String>,
+
+    /// Comma-separated
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(VALID_MEAL_TYPES))]
+    meal_type: Vec<String>,
+}
+
+/// Everything that goes into the frontmatter.
+#[derive(Debug)]
+pub(crate) struct Frontmatter {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) servings: u32,
+    pub(crate) time: u32,
+    pub(crate) difficulty: String,
+    pub(crate) diet: Vec<String>,
+    pub(crate) cuisine: Vec<String>,
+    pub(crate) meal_type: Vec<String>,
+    pub(crate) date: String,
+    pub(crate) tested: Option<bool>,
+}
+
+pub(crate) fn run(args: NewArgs, input: &Path, canonical: &CanonicalData) -> Result<()> {
+    let id = args.id.clone().unwrap_or_else(|| slugify(&args.name));
+    if id.is_empty() {
+        bail!("Couldn't derive an id from '{}'; pass one with --id", args.name);
+    }
+
+    let path = target_path(input, &id)?;
+
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    if !interactive {
+        let missing = missing_flags(&args);
+        if !missing.is_empty() {
+            bail!("Missing {} (or run in a terminal to be prompted)", missing.join(", "));
+        }
+    }
+    let frontmatter = complete(args, id, today(), &mut stdin.lock(), &mut std::io::stdout())?;
+
+    let source = render(&frontmatter);
+    let mut diagnostics = Diagnostics::default();
+    parse_recipe(&source, true, canonical, &mut diagnostics);
+    if let Some(d) = diagnostics.iter().find(|d| !SKELETON_ERRORS.contains(&d.rule)) {
+        bail!("The recipe header wouldn't pass validation: {}", d.message);
+    }
+
+    fs::write(&path, source)
+        .with_context(|| format!("Failed to write recipe: {:?}", path))?;
+    println!("✨ Created {}", path.display());
+    println!("   Fill in the Ingredients and Instructions, then run `npm run lint-recipes`.");
+    Ok(())
+}
+
+/// The flags that would otherwise be prompted for.
+fn missing_flags(args: &NewArgs) -> Vec<&'static str> {
+    [
+        ("--description", args.description.is_none()),
+        ("--servings", args.servings.is_none()),
+        ("--time", args.time.is_none()),
+        ("--difficulty", args.difficulty.is_none()),
+        ("--diet", args.diet.is_empty()),
+        ("--cuisine", args.cuisine.is_empty()),
+        ("--meal-type", args.meal_type.is_empty()),
+    ]
+    .into_iter()
+    .filter_map(|(flag, missing)| missing.then_some(flag))
+    .collect()
+}
+
+/// Fill in whatever wasn't passed as a flag by asking on `input`.
+fn complete(args: NewArgs, id: String, date: String, input: &mut impl BufRead, output: &mut impl Write) -> Result<Frontmatter> {
+    let mut ask = |question: &str, parse: &dyn Fn(&str) -> Result<String, String>| prompt(input, output, question, parse);
+
+    let description = match args.description {
+        Some(description) => description,
+        None => ask("Description (10-500 characters)", &|s| {
+            if (10..=500).contains(&s.len()) { Ok(s.to_string()) } else { Err("Use 10 to 500 characters".into()) }
+        })?,
+    };
+    let servings = match args.servings {
+        Some(servings) => servings,
+        None => ask("Servings (1-100)", &|s| number(s, 100))?.parse()?,
+    };
+    let time = match args.time {
+        Some(time) => time,
+        None => ask("Total time in minutes (1-1440)", &|s| number(s, 1440))?.parse()?,
+    };
+    let difficulty = match args.difficulty {
+        Some(difficulty) => difficulty,
+        None => ask(&format!("Difficulty ({})", VALID_DIFFICULTIES.join(", ")), &|s| one_of(s, VALID_DIFFICULTIES))?,
+    };
+    let mut list = |values: Vec<String>, field: &str, allowed: &[&str]| -> Result<Vec<String>> {
+        if !values.is_empty() {
+            return Ok(values);
+        }
+        let question = format!("{} ({}; comma-separated)", field, allowed.join(", "));
+        let answer = ask(&question, &|s| {
+            s.split(',').map(|v| one_of(v.trim(), allowed)).collect::<Result<Vec<_>, _>>().map(|v| v.join(","))
+        })?;
+        Ok(answer.split(',').map(str::to_string).collect())
+    };
+
+    Ok(Frontmatter {
+        id,
+        name: args.name,
+        description,
+        servings,
+        time,
+        difficulty,
+        diet: list(args.diet, "Diet", VALID_DIETS)?,
+        cuisine: list(args.cuisine, "Cuisine", VALID_CUISINES)?,
+        meal_type: list(args.meal_type, "Meal type", VALID_MEAL_TYPES)?,
+        date,
+        tested: None,
+    })
+}
+
+/// Ask until `parse` accepts the answer.
+fn prompt(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    parse: &dyn Fn(&str) -> Result<String, String>,
+) -> Result<String> {
+    loop {
+        write!(output, "{}: ", question)?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            bail!("No answer for '{}'", question);
+        }
+        match parse(line.trim()) {
+            Ok(value) => return Ok(value),
+            Err(e) => writeln!(output, "  {}", e)?,
+        }
+    }
+}
+
+fn number(s: &str, max: u32) -> Result<String, String> {
+    match s.parse::<u32>() {
+        Ok(n) if (1..=max).contains(&n) => Ok(n.to_string()),
+        _ => Err(format!("Enter a whole number from 1 to {}", max)),
+    }
+}
+
+fn one_of(s: &str, allowed: &[&str]) -> Result<String, String> {
+    let value = s.to_lowercase();
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(format!("'{}' isn't one of: {}", s, allowed.join(", ")))
+    }
+}
+
+/// Id for a recipe name: lowercase letters separated by single dashes. Accented letters are
+/// folded to their plain form and `&` becomes "and"; anything else separates words.
+pub(crate) fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        let letters = match c {
+            'a'..='z' => c.to_string(),
+            '&' => " and ".to_string(),
+            _ => fold_accent(c).unwrap_or(" ").to_string(),
+        };
+        for l in letters.chars() {
+            if l != ' ' {
+                slug.push(l);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    slug[..slug.len().min(100)].trim_end_matches('-').to_string()
+}
+
+fn fold_accent(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a",
+        'æ' => "ae",
+        'ç' => "c",
+        'è' | 'é' | 'ê' | 'ë' => "e",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'ñ' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => "o",
+        'œ' => "oe",
+        'ß' => "ss",
+        'ù' | 'ú' | 'û' | 'ü' => "u",
+        'ý' | 'ÿ' => "y",
+        _ => return None,
+    })
+}
+
+/// Where a new recipe with `id` goes, refusing ids that are already taken.
+pub(crate) fn target_path(input: &Path, id: &str) -> Result<PathBuf> {
+    let path = input.join(format!("{}.md", id));
+    if path.exists() {
+        bail!("{} already exists; pick another name or pass --id", path.display());
+    }
+    if let Some(existing) = find_id(input, id)? {
+        bail!("Recipe id '{}' is already used by {}; pick another name or pass --id", id, existing.display());
+    }
+    Ok(path)
+}
+
+/// The recipe file in `dir` that declares `id`, whatever it is called.
+fn find_id(dir: &Path, id: &str) -> Result<Option<PathBuf>> {
+    if !dir.exists() {
+        bail!("Input directory does not exist: {:?}", dir);
+    }
+    for path in recipe_paths(dir)? {
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read file: {:?}", path))?;
+        let declared = source.lines()
+            .take_while(|l| !l.starts_with('#'))
+            .find_map(|l| l.strip_prefix("id:"))
+            .map(|v| v.trim().trim_matches(['"', '\'']));
+        if declared == Some(id) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Today's date (UTC) as YYYY-MM-DD.
+pub(crate) fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86_400).unwrap_or_default();
+    civil_date(days as i64)
+}
+
+/// Gregorian date for a day count since 1970-01-01 (Howard Hinnant's `civil_from_days`).
+fn civil_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A frontmatter string value, quoted only if YAML would read it differently.
+fn yaml_string(s: &str) -> String {
+    match serde_yaml::from_str::<serde_yaml::Value>(s) {
+        Ok(serde_yaml::Value::String(parsed)) if parsed == s => s.to_string(),
+        _ => serde_json::to_string(s).expect("string serializes"),
+    }
+}
+
+fn render(fm: &Frontmatter) -> String {
+    format!("{}\n# Notes\n\n# Ingredients\n\n# Instructions\n\n# Serving Suggestions\n", render_frontmatter(fm))
+}
+
+pub(crate) fn render_frontmatter(fm: &Frontmatter) -> String {
+    format!(
+        "---
+id: {}
+name: {}
+description: {}
+servings: {}
+time: {}
+difficulty: {}
+diet: [{}]
+cuisine: [{}]
+meal_type: [{}]
+date: {}
+{}---
+",
+        fm.id,
+        yaml_string(&fm.name),
+        yaml_string(&fm.description),
+        fm.servings,
+        fm.time,
+        fm.difficulty,
+        fm.diet.join(", "),
+        fm.cuisine.join(", "),
+        fm.meal_type.join(", "),
+        fm.date,
+        fm.tested.map(|tested| format!("tested: {}\n", tested)).unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        args: NewArgs,
+    }
+
+    fn args(argv: &[&str]) -> NewArgs {
+        TestCli::try_parse_from(std::iter::once("new").chain(argv.iter().copied())).unwrap().args
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Thai Green Curry"), "thai-green-curry");
+        assert_eq!(slugify("  Mac 'n' Cheese!  "), "mac-n-cheese");
+        assert_eq!(slugify("Crème Brûlée"), "creme-brulee");
+        assert_eq!(slugify("Fish & Chips"), "fish-and-chips");
+        assert_eq!(slugify("5-Spice Tofu"), "spice-tofu");
+        assert_eq!(slugify("123"), "");
+        assert_eq!(slugify(&"abcd ".repeat(40)).len(), 99);
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+        assert_eq!(civil_date(20_743), "2026-10-17");
+    }
+
+    #[test]
+    fn test_prompts_for_missing_values() {
+        let args = args(&["Thai Green Curry", "--servings", "4", "--diet", "vegan,gluten-free"]);
+        let mut input = "Fragrant coconut curry with vegetables\n45\nsimple\nmedium\nthai\nAsian\ndinner, lunch\n".as_bytes();
+        let mut output = Vec::new();
+        let fm = complete(args, "thai-green-curry".into(), "2026-10-17".into(), &mut input, &mut output).unwrap();
+
+        assert_eq!(fm.servings, 4);
+        assert_eq!(fm.time, 45);
+        assert_eq!(fm.difficulty, "medium");
+        assert_eq!(fm.diet, vec!["vegan", "gluten-free"]);
+        assert_eq!(fm.cuisine, vec!["asian"]);
+        assert_eq!(fm.meal_type, vec!["dinner", "lunch"]);
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Difficulty (easy, medium, hard): "));
+        assert!(output.contains("'simple' isn't one of: easy, medium, hard"));
+        assert!(output.contains("'thai' isn't one of: indian, middle-eastern"));
+        assert!(!output.contains("Servings"));
+    }
+
+    #[test]
+    fn test_flags_only_accept_allowed_values() {
+        let parse = |argv: &[&str]| TestCli::try_parse_from(std::iter::once("new").chain(argv.iter().copied())).is_ok();
+        assert!(parse(&["Dal", "--diet", "vegan,vegetarian", "--meal-type", "dinner"]));
+        assert!(!parse(&["Dal", "--diet", "paleo"]));
+        assert!(!parse(&["Dal", "--difficulty", "simple"]));
+        assert!(!parse(&["Dal", "--servings", "0"]));
+        assert_eq!(missing_flags(&args(&["Dal", "--servings", "2", "--cuisine", "indian"])),
+            vec!["--description", "--time", "--difficulty", "--diet", "--meal-type"]);
+    }
+
+    #[test]
+    fn test_scaffold_only_lacks_ingredients_and_steps() {
+        let argv = [
+            "Mac: The Cheesening", "--description", "Baked pasta in a sharp cheese sauce", "--servings", "4",
+            "--time", "50", "--difficulty", "easy", "--diet", "vegetarian", "--cuisine", "american", "--meal-type", "dinner",
+        ];
+        let fm = complete(args(&argv), slugify(argv[0]), "2026-10-17".into(), &mut "".as_bytes(), &mut Vec::new()).unwrap();
+        let source = render(&fm);
+        assert!(source.contains("name: \"Mac: The Cheesening\"\n"));
+        assert!(source.contains("\n# Notes\n\n# Ingredients\n\n# Instructions\n\n# Serving Suggestions\n"));
+
+        let mut diagnostics = Diagnostics::default();
+        parse_recipe(&source, true, &CanonicalData::empty(), &mut diagnostics);
+        let mut rules: Vec<&str> = diagnostics.iter().map(|d| d.rule).collect();
+        rules.sort();
+        assert_eq!(rules, SKELETON_ERRORS);
+
+        // Already in the house layout
+        assert_eq!(crate::fmt::format_recipe(&source, &CanonicalData::empty()).unwrap(), source);
+    }
+
+    #[test]
+    fn test_refuses_existing_id() {
+        let dir = std::env::temp_dir().join(format!("recipe-new-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("curry.md"), "---\nid: thai-green-curry\nname: Curry\n---\n").unwrap();
+        let argv = [
+            "Thai Green Curry", "--description", "Fragrant coconut curry", "--servings", "4", "--time", "45",
+            "--difficulty", "easy", "--diet", "vegan", "--cuisine", "asian", "--meal-type", "dinner",
+        ];
+
+        let err = run(args(&argv), &dir, &CanonicalData::empty()).unwrap_err().to_string();
+        assert!(err.contains("already used by"), "{}", err);
+
+        fs::write(dir.join("pad-thai.md"), "").unwrap();
+        let err = run(args(&["Pad Thai"]), &dir, &CanonicalData::empty()).unwrap_err().to_string();
+        assert!(err.contains("already exists"), "{}", err);
+        assert_eq!(fs::read_to_string(dir.join("pad-thai.md")).unwrap(), "");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
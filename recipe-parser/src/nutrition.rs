@@ -0,0 +1,265 @@
+//! Per-serving nutrition, estimated from the per-100 g table in `ingredients.json`, and the
+//! `nutrition` command that reports which ingredients lack the data to be counted.
+//!
+//! A quantity is weighed from its unit: mass units directly, volume units through the
+//! ingredient's density, and counts ("1 onion", "2 cloves") through the ingredient's `grams`.
+//! When the unit can't be weighed, an explicit secondary weight ("1 tin (400 g)") is used.
+//! Ingredients without a quantity ("Salt to taste") are left out of the estimate and of its
+//! coverage, as is oil for deep- or shallow-frying, most of which stays in the pan.
+
+use crate::{parse_all, recipe_paths, CanonicalData, Dimension, ParsedQuantity, Recipe, VALID_CATEGORIES};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Key of `grams` for a count without a unit, as in "2 carrots".
+pub(crate) const EACH: &str = "each";
+
+/// Preparations of ingredients that are mostly not eaten.
+const FRYING: [&str; 2] = ["deep-frying", "shallow-frying"];
+
+/// Nutrients per 100 g in the vocabulary, or per serving in a recipe. Carbohydrates exclude
+/// fibre, as on UK/EU labels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Nutrients {
+    pub(crate) kcal: f64,
+    pub(crate) protein: f64,
+    pub(crate) fat: f64,
+    pub(crate) carbs: f64,
+    pub(crate) fibre: f64,
+    pub(crate) salt: f64,
+}
+
+impl Nutrients {
+    fn add(&mut self, other: &Nutrients, ratio: f64) {
+        self.kcal += other.kcal * ratio;
+        self.protein += other.protein * ratio;
+        self.fat += other.fat * ratio;
+        self.carbs += other.carbs * ratio;
+        self.fibre += other.fibre * ratio;
+        self.salt += other.salt * ratio;
+    }
+
+    /// Whole kcal, grams to 1 decimal and salt to 2.
+    fn rounded(self) -> Self {
+        let grams = |n: f64| (n * 10.0).round() / 10.0;
+        Self {
+            kcal: self.kcal.round(),
+            protein: grams(self.protein),
+            fat: grams(self.fat),
+            carbs: grams(self.carbs),
+            fibre: grams(self.fibre),
+            salt: (self.salt * 100.0).round() / 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Nutrition {
+    /// Sum over the ingredients that could be weighed and have nutrition data
+    pub(crate) per_serving: Nutrients,
+    /// Percentage of the ingredients with a quantity that are in `per_serving`
+    pub(crate) coverage: u32,
+}
+
+/// Why an ingredient line isn't in a recipe's estimate.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Missing {
+    /// No `nutrition` for the ingredient in the vocabulary
+    Nutrition,
+    /// A volume unit, but no density for the ingredient
+    Density(String),
+    /// A count unit (or `EACH`) without `grams` for the ingredient
+    Grams(String),
+    /// Only part of the item is used, as in "Juice of 1 lemon"
+    Part(String),
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Missing::Nutrition => write!(f, "no nutrition per 100 g"),
+            Missing::Density(unit) => write!(f, "no density to weigh '{}'", unit),
+            Missing::Grams(unit) if unit == EACH => write!(f, "no grams for one, unitless"),
+            Missing::Grams(unit) => write!(f, "no grams for one '{}'", unit),
+            Missing::Part(prefix) => write!(f, "'{}' uses only part of it", prefix),
+        }
+    }
+}
+
+/// Grams of `amount` in `unit` of the ingredient `name`.
+fn weigh(amount: f64, unit: Option<&str>, name: &str, canonical: &CanonicalData) -> Result<f64, Missing> {
+    let Some(unit) = unit else {
+        return canonical.ingredient_grams.get(name).and_then(|grams| grams.get(EACH))
+            .map(|grams| amount * grams)
+            .ok_or_else(|| Missing::Grams(EACH.to_string()));
+    };
+    let singular = canonical.unit_singular(unit);
+    match canonical.unit_dimension(unit) {
+        Some((Dimension::Mass, factor)) => Ok(amount * factor),
+        Some((Dimension::Volume, factor)) => canonical.ingredient_densities.get(name)
+            .map(|density| amount * factor * density)
+            .ok_or_else(|| Missing::Density(singular.to_string())),
+        _ => canonical.ingredient_grams.get(name).and_then(|grams| grams.get(singular))
+            .map(|grams| amount * grams)
+            .ok_or_else(|| Missing::Grams(singular.to_string())),
+    }
+}
+
+/// Grams of the ingredient in `q`, from its unit or else its secondary quantity.
+pub(crate) fn grams(q: &ParsedQuantity, name: &str, canonical: &CanonicalData) -> Result<f64, Missing> {
+    if let Some(prefix) = &q.prefix {
+        return Err(Missing::Part(prefix.clone()));
+    }
+    // The middle of a range
+    let amount = (q.amount + q.amount_max.unwrap_or(q.amount)) / 2.0;
+    weigh(amount, q.unit.as_deref(), name, canonical).or_else(|missing| match q.secondary_amount {
+        Some(secondary) if q.secondary_unit.is_some() => {
+            weigh(secondary, q.secondary_unit.as_deref(), name, canonical).map_err(|_| missing)
+        }
+        _ => Err(missing),
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Estimate {
+    /// `None` when not a single ingredient could be counted
+    pub(crate) nutrition: Option<Nutrition>,
+    /// The ingredients left out, by canonical name (or lowercased text), in recipe order
+    pub(crate) missing: Vec<(String, Missing)>,
+}
+
+pub(crate) fn estimate(recipe: &Recipe, canonical: &CanonicalData) -> Estimate {
+    let mut total = Nutrients::default();
+    let (mut counted, mut quantities) = (0, 0);
+    let mut missing = Vec::new();
+    for &category in VALID_CATEGORIES {
+        for ingredient in recipe.ingredients.get(category).into_iter().flatten() {
+            let Some(q) = &ingredient.quantity else { continue };
+            if ingredient.preparation.as_deref().is_some_and(|p| FRYING.iter().any(|f| p.contains(f))) {
+                continue;
+            }
+            quantities += 1;
+            let name = ingredient.canonical.clone().unwrap_or_else(|| ingredient.text.to_lowercase());
+            let Some(per_100g) = canonical.ingredient_nutrition.get(&name) else {
+                missing.push((name, Missing::Nutrition));
+                continue;
+            };
+            match grams(q, &name, canonical) {
+                Ok(grams) => {
+                    total.add(per_100g, grams / 100.0 / recipe.servings as f64);
+                    counted += 1;
+                }
+                Err(reason) => missing.push((name, reason)),
+            }
+        }
+    }
+    let nutrition = (counted > 0).then(|| Nutrition {
+        per_serving: total.rounded(),
+        coverage: (counted as f64 * 100.0 / quantities as f64).round() as u32,
+    });
+    Estimate { nutrition, missing }
+}
+
+/// The `nutrition` subcommand: each recipe's estimate and coverage, then the missing data
+/// across all recipes, the ingredient lines it would add first. Fails when a recipe's
+/// coverage is below `min_coverage`.
+pub(crate) fn run(input: &Path, min_coverage: Option<u32>, canonical: &CanonicalData) -> Result<()> {
+    let paths = recipe_paths(input)?;
+    let recipes: Vec<Recipe> = parse_all(&paths, false, canonical, None).into_iter().filter_map(|(recipe, _, _)| recipe).collect();
+    if recipes.len() < paths.len() {
+        eprintln!("⚠️  Skipped {} recipe(s) that don't parse; run the parser for details", paths.len() - recipes.len());
+    }
+
+    // (ingredient, reason) → recipe ids
+    let mut gaps: BTreeMap<(String, Missing), Vec<String>> = BTreeMap::new();
+    let mut below = Vec::new();
+    println!("🥗 Nutrition per serving");
+    for recipe in &recipes {
+        let estimate = estimate(recipe, canonical);
+        match &estimate.nutrition {
+            Some(Nutrition { per_serving: n, coverage }) => println!(
+                "  {:>3}%  {}: {} kcal · protein {} g · fat {} g · carbs {} g · fibre {} g · salt {} g",
+                coverage, recipe.id, n.kcal, n.protein, n.fat, n.carbs, n.fibre, n.salt,
+            ),
+            None => println!("    0%  {}: no ingredient has nutrition data", recipe.id),
+        }
+        let coverage = estimate.nutrition.as_ref().map_or(0, |n| n.coverage);
+        if min_coverage.is_some_and(|min| coverage < min) {
+            below.push(recipe.id.clone());
+        }
+        for gap in estimate.missing {
+            gaps.entry(gap).or_default().push(recipe.id.clone());
+        }
+    }
+
+    if !gaps.is_empty() {
+        let mut gaps: Vec<_> = gaps.into_iter().collect();
+        gaps.sort_by_key(|(_, recipes)| std::cmp::Reverse(recipes.len()));
+        println!("\n🔎 Missing data, {} gap(s)", gaps.len());
+        for ((name, reason), ids) in gaps {
+            println!("  {} ×{}: {} ({})", name, ids.len(), reason, ids.join(", "));
+        }
+    }
+    if !below.is_empty() {
+        bail!("{} recipe(s) below {}% coverage: {}", below.len(), min_coverage.unwrap_or_default(), below.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{vocabulary, TestRecipe};
+    use std::collections::HashMap;
+
+    fn canonical() -> CanonicalData {
+        let mut data = vocabulary(
+            &[("lentil", "Pantry"), ("onion", "Fresh"), ("oat milk", "Fridge"), ("chickpea", "Pantry"), ("lemon", "Fresh"), ("cumin", "Spices")],
+            &[("lentils", "lentil"), ("onions", "onion"), ("chickpeas", "chickpea")],
+        );
+        let per_100g = |kcal, protein| Nutrients { kcal, protein, fat: 1.0, carbs: 10.0, fibre: 2.0, salt: 0.1 };
+        for (name, nutrients) in [("lentil", per_100g(320.0, 24.0)), ("onion", per_100g(40.0, 1.0)), ("oat milk", per_100g(45.0, 1.0)),
+            ("chickpea", per_100g(120.0, 7.0)), ("lemon", per_100g(30.0, 1.0))] {
+            data.ingredient_nutrition.insert(name.to_string(), nutrients);
+        }
+        data.ingredient_grams.insert("onion".to_string(), HashMap::from([(EACH.to_string(), 150.0), ("medium".to_string(), 150.0)]));
+        data.ingredient_densities.insert("oat milk".to_string(), 1.0);
+        data
+    }
+
+    fn recipe(servings: u32, ingredients: &str) -> Recipe {
+        TestRecipe::new("dal").set("servings", servings).ingredients(ingredients).parse(&canonical())
+    }
+
+    #[test]
+    fn test_estimate_per_serving() {
+        let recipe = recipe(2, "- 200 g [lentils]\n- 1-3 [onions]\n- 250 ml [oat milk]\n- 1 tin (400 g) [chickpeas]\n- 1 tsp [cumin]\n- Juice of 1 [lemon]\n- [Salt] to taste\n- 500 ml [oat milk], for deep-frying");
+        let estimate = estimate(&recipe, &canonical());
+        // Per serving: 100 g lentil, 150 g onion (middle of the range), 125 g oat milk, 200 g chickpea
+        let nutrition = estimate.nutrition.unwrap();
+        assert_eq!(nutrition.per_serving.kcal, 676.0);
+        assert_eq!(nutrition.per_serving.protein, 40.8);
+        // 4 of the 6 quantities; salt has none, and the frying oat milk doesn't count
+        assert_eq!(nutrition.coverage, 67);
+        assert_eq!(estimate.missing, vec![
+            ("lemon".to_string(), Missing::Part("Juice of".to_string())),
+            ("cumin".to_string(), Missing::Nutrition),
+        ]);
+    }
+
+    #[test]
+    fn test_missing_weights() {
+        let recipe = recipe(1, "- 2 tbsp [lentils]\n- 1 large [onion]");
+        let estimate = estimate(&recipe, &canonical());
+        assert_eq!(estimate.nutrition, None);
+        assert_eq!(estimate.missing, vec![
+            ("onion".to_string(), Missing::Grams("large".to_string())),
+            ("lentil".to_string(), Missing::Density("tbsp".to_string())),
+        ]);
+        assert_eq!(estimate.missing[0].1.to_string(), "no grams for one 'large'");
+    }
+}
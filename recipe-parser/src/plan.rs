@@ -0,0 +1,266 @@
+//! `plan`: pick recipes for several days that share as many ingredients as possible, the same
+//! greedy IDF selection as the planner in `docs/js/plan.js`, plus a merged shopping list.
+
+use crate::shopping::{self, Section};
+use crate::similar::{idf, ingredient_sections, pair_score};
+use crate::{parse_all, recipe_paths, CanonicalData, Recipe, VALID_DIETS, VALID_MEAL_TYPES};
+use anyhow::{bail, Result};
+use clap::builder::PossibleValuesParser;
+use serde::Serialize;
+use std::path::Path;
+
+/// Shared ingredients listed for a plan.
+const SHARED_SHOWN: usize = 8;
+
+#[derive(clap::Args)]
+pub(crate) struct PlanArgs {
+    /// Number of recipes to pick
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..=14))]
+    days: u32,
+
+    /// Only recipes with all of these diets; comma-separated
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(VALID_DIETS))]
+    diet: Vec<String>,
+
+    /// Only recipes for any of these meals; comma-separated
+    #[arg(long, value_delimiter = ',', default_value = "lunch,dinner", value_parser = PossibleValuesParser::new(VALID_MEAL_TYPES))]
+    meal_type: Vec<String>,
+
+    /// Recipe ids that must be in the plan; the rest is picked around them. Comma-separated
+    #[arg(long, value_delimiter = ',')]
+    seed: Vec<String>,
+
+    /// Only recipes taking at most this many minutes
+    #[arg(long)]
+    max_time: Option<u32>,
+
+    /// Also consider recipes that are marked `tested: false`
+    #[arg(long)]
+    untested: bool,
+
+    /// Print the plan as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PlannedRecipe<'a> {
+    id: &'a str,
+    name: &'a str,
+    time: u32,
+    servings: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct Shared {
+    canonical: String,
+    recipes: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Plan<'a> {
+    recipes: Vec<PlannedRecipe<'a>>,
+    shared: Vec<Shared>,
+    shopping_list: Vec<Section>,
+}
+
+fn matches(recipe: &Recipe, args: &PlanArgs) -> bool {
+    (args.untested || recipe.tested)
+        && args.diet.iter().all(|d| recipe.diet.contains(d))
+        && recipe.meal_type.iter().any(|m| args.meal_type.contains(m))
+        && args.max_time.is_none_or(|max| recipe.time <= max)
+}
+
+/// Greedily pick `count` of `maps`' recipes by position: the seeds (or else the best-scoring
+/// pair), then whichever recipe adds the most to its scores with those already picked.
+fn suggest(count: usize, maps: &[Vec<(String, String)>], seeds: &[usize]) -> Vec<usize> {
+    if maps.len() <= count {
+        return (0..maps.len()).collect();
+    }
+    let idf = idf(maps);
+    let score = |a: usize, b: usize| pair_score(&maps[a], &maps[b], &idf).0;
+
+    let mut selected = seeds.to_vec();
+    if selected.is_empty() {
+        let mut best = ((0, 1), -1.0);
+        for a in 0..maps.len() {
+            for b in a + 1..maps.len() {
+                let s = score(a, b);
+                if s > best.1 {
+                    best = ((a, b), s);
+                }
+            }
+        }
+        selected = vec![best.0 .0, best.0 .1];
+    }
+    while selected.len() < count {
+        let mut best = (usize::MAX, -1.0);
+        for candidate in (0..maps.len()).filter(|c| !selected.contains(c)) {
+            let s: f64 = selected.iter().map(|&picked| score(picked, candidate)).sum();
+            if s > best.1 {
+                best = (candidate, s);
+            }
+        }
+        selected.push(best.0);
+    }
+    selected.truncate(count);
+    selected
+}
+
+/// Ingredients in at least two of the planned recipes, most shared first.
+fn shared_ingredients(planned: &[&Vec<(String, String)>]) -> Vec<Shared> {
+    let mut counts: Vec<Shared> = Vec::new();
+    for map in planned {
+        for (name, _) in map.iter() {
+            match counts.iter_mut().find(|s| s.canonical == *name) {
+                Some(shared) => shared.recipes += 1,
+                None => counts.push(Shared { canonical: name.clone(), recipes: 1 }),
+            }
+        }
+    }
+    counts.retain(|s| s.recipes >= 2);
+    counts.sort_by_key(|s| std::cmp::Reverse(s.recipes));
+    counts.truncate(SHARED_SHOWN);
+    counts
+}
+
+fn make_plan<'a>(recipes: &'a [Recipe], args: &PlanArgs, canonical: &CanonicalData) -> Result<Plan<'a>> {
+    let corpus: Vec<&Recipe> = recipes.iter().filter(|r| matches(r, args)).collect();
+    let mut seeds = Vec::new();
+    for id in &args.seed {
+        match corpus.iter().position(|r| r.id == *id) {
+            Some(position) if seeds.contains(&position) => bail!("'{}' is listed twice", id),
+            Some(position) => seeds.push(position),
+            None if recipes.iter().any(|r| r.id == *id) => bail!("Seed recipe '{}' doesn't match the plan's filters", id),
+            None => bail!("Unknown seed recipe '{}'", id),
+        }
+    }
+    if seeds.len() > args.days as usize {
+        bail!("{} seed recipes don't fit in {} days", seeds.len(), args.days);
+    }
+
+    let maps: Vec<Vec<(String, String)>> = corpus.iter().map(|r| ingredient_sections(r, canonical)).collect();
+    let picked = suggest(args.days as usize, &maps, &seeds);
+    let chosen: Vec<&Recipe> = picked.iter().map(|&i| corpus[i]).collect();
+    Ok(Plan {
+        recipes: chosen.iter().map(|r| PlannedRecipe { id: &r.id, name: &r.name, time: r.time, servings: r.servings }).collect(),
+        shared: shared_ingredients(&picked.iter().map(|&i| &maps[i]).collect::<Vec<_>>()),
+        shopping_list: shopping::merge(&chosen.iter().map(|&r| (r, 1.0)).collect::<Vec<_>>(), canonical),
+    })
+}
+
+fn print_text(plan: &Plan) {
+    println!("🗓️  {}-day plan", plan.recipes.len());
+    for (day, recipe) in plan.recipes.iter().enumerate() {
+        println!("  {}. {} ({}) · {} min · serves {}", day + 1, recipe.name, recipe.id, recipe.time, recipe.servings);
+    }
+    if !plan.shared.is_empty() {
+        let shared: Vec<String> = plan.shared.iter().map(|s| format!("{} ×{}", s.canonical, s.recipes)).collect();
+        println!("\n🔗 Shared: {}", shared.join(", "));
+    }
+    println!("\n🛒 Shopping list");
+    for section in &plan.shopping_list {
+        println!("  {}", section.section);
+        for item in &section.items {
+            println!("    - {}", item.text);
+        }
+    }
+}
+
+pub(crate) fn run(args: &PlanArgs, input: &Path, canonical: &CanonicalData) -> Result<()> {
+    let paths = recipe_paths(input)?;
+    let recipes: Vec<Recipe> = parse_all(&paths, false, canonical, None).into_iter().filter_map(|(recipe, _, _)| recipe).collect();
+    if recipes.len() < paths.len() {
+        eprintln!("⚠️  Skipped {} recipe(s) that don't parse; run the parser for details", paths.len() - recipes.len());
+    }
+    let plan = make_plan(&recipes, args, canonical)?;
+    if plan.recipes.len() < args.days as usize {
+        eprintln!("⚠️  Only {} recipe(s) match the filters", plan.recipes.len());
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+    } else {
+        print_text(&plan);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{vocabulary, TestRecipe};
+    use clap::Parser;
+
+    #[derive(clap::Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: PlanArgs,
+    }
+
+    fn args(flags: &[&str]) -> PlanArgs {
+        Cli::parse_from(["plan"].iter().chain(flags)).args
+    }
+
+    fn canonical() -> CanonicalData {
+        vocabulary(&[("garlic", "Fresh"), ("lentil", "Pantry"), ("tahini", "Condiments"), ("rice", "Pantry"),
+            ("spinach", "Fresh"), ("egg", "Fridge")], &[])
+    }
+
+    /// A recipe of one of each `ingredients`, with `frontmatter` fields replacing the defaults.
+    fn recipe(id: &str, frontmatter: &[(&'static str, &str)], ingredients: &[&str]) -> Recipe {
+        let lines: Vec<String> = ingredients.iter().map(|name| format!("- 1 [{}]", name)).collect();
+        let recipe = frontmatter.iter().fold(TestRecipe::new(id), |recipe, (key, value)| recipe.set(key, value));
+        recipe.ingredients(&lines.join("\n")).parse(&canonical())
+    }
+
+    fn recipes() -> Vec<Recipe> {
+        let main = &[("time", "30")];
+        vec![
+            recipe("dal", main, &["garlic", "lentil", "spinach"]),
+            recipe("salad", main, &["garlic", "lentil", "tahini"]),
+            recipe("hummus", &[("meal_type", "[lunch]")], &["garlic", "tahini"]),
+            recipe("pilaf", main, &["rice", "spinach"]),
+            recipe("omelette", &[("diet", "[vegetarian]")], &["egg", "spinach"]),
+            recipe("cake", &[("time", "60"), ("meal_type", "[dessert]")], &["lentil", "garlic"]),
+            recipe("soup", &[("time", "30"), ("tested", "false")], &["garlic", "lentil", "tahini"]),
+        ]
+    }
+
+    fn ids(plan: &Plan) -> Vec<String> {
+        plan.recipes.iter().map(|r| r.id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plan_filters_and_picks_by_overlap() {
+        let recipes = recipes();
+        let canonical = canonical();
+
+        // Desserts and untested recipes are out by default. Salad and hummus share the rarer
+        // tahini, then dal adds the most to both
+        let plan = make_plan(&recipes, &args(&["--days", "3"]), &canonical).unwrap();
+        assert_eq!(ids(&plan), vec!["salad", "hummus", "dal"]);
+        assert_eq!(plan.shared[0].canonical, "garlic");
+        assert_eq!(plan.shared[0].recipes, 3);
+
+        let plan = make_plan(&recipes, &args(&["--days", "2", "--diet", "vegan", "--seed", "pilaf"]), &canonical).unwrap();
+        assert_eq!(ids(&plan), vec!["pilaf", "dal"]);
+        assert_eq!(plan.shopping_list[0].items[0].lines.len(), 2);
+
+        let plan = make_plan(&recipes, &args(&["--days", "5", "--max-time", "15"]), &canonical).unwrap();
+        assert_eq!(ids(&plan), vec!["hummus", "omelette"]);
+
+        let plan = make_plan(&recipes, &args(&["--days", "1", "--meal-type", "lunch"]), &canonical).unwrap();
+        assert_eq!(ids(&plan), vec!["hummus"]);
+    }
+
+    #[test]
+    fn test_plan_rejects_bad_seeds() {
+        let recipes = recipes();
+        let err = make_plan(&recipes, &args(&["--seed", "cake"]), &canonical()).unwrap_err();
+        assert!(err.to_string().contains("doesn't match"));
+        assert!(make_plan(&recipes, &args(&["--seed", "toast"]), &canonical()).unwrap_err().to_string().contains("Unknown"));
+        assert!(make_plan(&recipes, &args(&["--seed", "soup", "--untested"]), &canonical()).is_ok());
+        let err = make_plan(&recipes, &args(&["--days", "3", "--seed", "dal,dal"]), &canonical()).unwrap_err();
+        assert_eq!(err.to_string(), "'dal' is listed twice");
+    }
+}
@@ -0,0 +1,205 @@
+//! `schema`: the JSON Schema of `recipes.json`, i.e. the shape of the serialized `Recipe`,
+//! `Ingredient`, `ParsedQuantity` and `Step` that the frontend in `docs/js` relies on.
+//!
+//! The schema is written by hand rather than derived from the structs, so that it stays a
+//! contract: a struct change that alters the output fails the test that validates the real
+//! recipes against it. Bump `SCHEMA_VERSION` whenever the schema itself has to change.
+
+use crate::{VALID_CATEGORIES, VALID_CUISINES, VALID_DIETS, VALID_DIFFICULTIES, VALID_MEAL_TYPES};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Version of the `recipes.json` shape, published in `recipes-manifest.json`.
+pub(crate) const SCHEMA_VERSION: u32 = 4;
+
+/// Print the schema to stdout.
+pub(crate) fn run() -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&recipes_schema())?);
+    Ok(())
+}
+
+pub(crate) fn recipes_schema() -> Value {
+    let text = json!({ "type": "string", "minLength": 1 });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("BiteMe recipes, schema version {}", SCHEMA_VERSION),
+        "description": "docs/recipes.json: every recipe, newest first",
+        "type": "array",
+        "items": { "$ref": "#/$defs/recipe" },
+        "$defs": {
+            "recipe": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "pattern": "^[a-z]+(-[a-z]+)*$", "maxLength": 100 },
+                    "name": text,
+                    "description": text,
+                    "servings": { "type": "integer", "minimum": 1 },
+                    "time": { "type": "integer", "minimum": 1, "description": "Total time in minutes" },
+                    "difficulty": { "enum": VALID_DIFFICULTIES },
+                    "diet": { "type": "array", "items": { "enum": VALID_DIETS }, "uniqueItems": true },
+                    "cuisine": { "type": "array", "items": { "enum": VALID_CUISINES }, "uniqueItems": true },
+                    "meal_type": { "type": "array", "items": { "enum": VALID_MEAL_TYPES }, "uniqueItems": true },
+                    "date": { "type": "string", "pattern": "^\\d{4}-\\d{2}-\\d{2}$" },
+                    "notes": text,
+                    "ingredients": {
+                        "type": "object",
+                        "description": "Ingredients by shopping section, in section order",
+                        "propertyNames": { "enum": VALID_CATEGORIES },
+                        "additionalProperties": { "type": "array", "items": { "$ref": "#/$defs/ingredient" } },
+                    },
+                    "steps": { "type": "array", "items": { "$ref": "#/$defs/step" }, "minItems": 1 },
+                    "serving_suggestions": text,
+                    "tested": { "type": "boolean" },
+                    "nutrition": { "$ref": "#/$defs/nutrition" },
+                },
+                "required": ["id", "name", "description", "servings", "time", "difficulty", "diet", "cuisine",
+                    "meal_type", "date", "ingredients", "steps", "tested"],
+                "additionalProperties": false,
+            },
+            "ingredient": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "minimum": 0, "description": "Unique within the recipe" },
+                    "text": { "type": "string", "description": "The ingredient line without [brackets]" },
+                    "canonical": { "type": "string", "description": "Singular vocabulary name" },
+                    "preparation": { "type": "string" },
+                    "quantity": { "$ref": "#/$defs/quantity" },
+                },
+                "required": ["id", "text"],
+                "additionalProperties": false,
+            },
+            "quantity": {
+                "type": "object",
+                "description": "Scalable quantity; absent for ingredients like \"Salt to taste\"",
+                "properties": {
+                    "amount": { "type": "number", "exclusiveMinimum": 0 },
+                    "amount_max": { "type": "number", "exclusiveMinimum": 0 },
+                    "unit": { "type": "string", "description": "As written, e.g. \"cloves\"" },
+                    "canonical_unit": { "type": "string", "description": "Singular vocabulary unit, e.g. \"clove\"" },
+                    "dimension": { "enum": ["mass", "volume", "count"] },
+                    "item": { "type": "string" },
+                    "secondary_amount": { "type": "number", "exclusiveMinimum": 0 },
+                    "secondary_unit": { "type": "string" },
+                    "secondary_prefix": { "type": "string" },
+                    "prefix": { "type": "string" },
+                    "imperial": { "$ref": "#/$defs/imperial" },
+                },
+                "required": ["amount", "item"],
+                "additionalProperties": false,
+            },
+            "imperial": {
+                "type": "object",
+                "description": "US-customary alternative of a metric quantity, with --imperial",
+                "properties": {
+                    "amount": { "type": "number", "exclusiveMinimum": 0 },
+                    "amount_max": { "type": "number", "exclusiveMinimum": 0 },
+                    "unit": { "enum": ["oz", "lb", "fl oz", "cup", "tbsp", "tsp"] },
+                    "text": { "type": "string", "description": "e.g. \"1 1/2 cups\"" },
+                },
+                "required": ["amount", "unit", "text"],
+                "additionalProperties": false,
+            },
+            "nutrition": {
+                "type": "object",
+                "description": "Per-serving estimate from the vocabulary's nutrition table",
+                "properties": {
+                    "per_serving": {
+                        "type": "object",
+                        "properties": {
+                            "kcal": { "type": "number", "minimum": 0 },
+                            "protein": { "type": "number", "minimum": 0, "description": "Grams, as are the rest" },
+                            "fat": { "type": "number", "minimum": 0 },
+                            "carbs": { "type": "number", "minimum": 0, "description": "Excluding fibre" },
+                            "fibre": { "type": "number", "minimum": 0 },
+                            "salt": { "type": "number", "minimum": 0 },
+                        },
+                        "required": ["kcal", "protein", "fat", "carbs", "fibre", "salt"],
+                        "additionalProperties": false,
+                    },
+                    "coverage": { "type": "integer", "minimum": 1, "maximum": 100, "description": "Percentage of the ingredients with a quantity that are counted" },
+                },
+                "required": ["per_serving", "coverage"],
+                "additionalProperties": false,
+            },
+            "step": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Step text with {ingredient} references" },
+                    "durations": { "type": "array", "items": { "$ref": "#/$defs/duration" }, "minItems": 1 },
+                },
+                "required": ["text"],
+                "additionalProperties": false,
+            },
+            "duration": {
+                "type": "object",
+                "properties": {
+                    "seconds": { "type": "integer", "minimum": 1 },
+                    "text": { "type": "string", "description": "The matched text, for timer buttons" },
+                },
+                "required": ["seconds", "text"],
+                "additionalProperties": false,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_canonical, parse_all, recipe_paths};
+    use std::path::Path;
+
+    fn errors(instance: &Value) -> Vec<String> {
+        let validator = jsonschema::draft202012::new(&recipes_schema()).expect("schema is valid");
+        validator.iter_errors(instance).map(|e| format!("{}: {}", e.instance_path, e)).collect()
+    }
+
+    #[test]
+    fn test_recipes_json_matches_schema() {
+        // The real recipes, serialized exactly as write_outputs does
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let canonical = load_canonical(&root.join(crate::VOCABULARY_PATH)).unwrap();
+        let paths = recipe_paths(&root.join("recipes")).unwrap();
+        let recipes: Vec<_> = parse_all(&paths, false, &canonical, None).into_iter().filter_map(|(recipe, _, _)| recipe).collect();
+        assert_eq!(recipes.len(), paths.len());
+
+        let json: Value = serde_json::from_str(&serde_json::to_string(&recipes).unwrap()).unwrap();
+        assert_eq!(errors(&json), Vec::<String>::new());
+
+        // And with --imperial
+        let mut recipes = recipes;
+        crate::imperial::annotate(&mut recipes, &canonical);
+        let json: Value = serde_json::from_str(&serde_json::to_string(&recipes).unwrap()).unwrap();
+        assert_eq!(errors(&json), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_schema_rejects_shape_changes() {
+        let recipe = json!({
+            "id": "dal", "name": "Dal", "description": "Lentils", "servings": 4, "time": 30,
+            "difficulty": "easy", "diet": ["vegan"], "cuisine": ["indian"], "meal_type": ["dinner"],
+            "date": "2025-01-01", "tested": true,
+            "ingredients": { "Pantry": [{ "id": 0, "text": "250 g lentils", "canonical": "lentil",
+                "quantity": { "amount": 250.0, "unit": "g", "item": "lentils" } }] },
+            "steps": [{ "text": "Cook the {lentils} for 20 minutes.", "durations": [{ "seconds": 1200, "text": "for 20 minutes" }] }],
+        });
+        assert!(errors(&json!([recipe])).is_empty());
+
+        let broken = |path: &str, value: Option<Value>| {
+            let mut copy = recipe.clone();
+            let (parent, key) = path.rsplit_once('/').unwrap();
+            let target = copy.pointer_mut(parent).unwrap().as_object_mut().unwrap();
+            match value {
+                Some(value) => target.insert(key.to_string(), value),
+                None => target.remove(key),
+            };
+            errors(&json!([copy]))
+        };
+        assert!(!broken("/servings", Some(json!("4"))).is_empty());
+        assert!(!broken("/tested", None).is_empty());
+        assert!(!broken("/ingredients/Other", Some(json!([]))).is_empty());
+        assert!(!broken("/ingredients/Pantry/0/quantity/amount", Some(json!("250"))).is_empty());
+        assert!(!broken("/steps/0/duration", Some(json!(20))).is_empty());
+        assert!(!broken("/difficulty", Some(json!("tricky"))).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+//! Prebuilt search index, written next to the manifest as `search-index.json`, and the
+//! `search` command that ranks recipes with it.
+//!
+//! Every field is tokenised the same way as queries: lowercased words, stopwords dropped and
+//! plural ingredient names folded to their singular through the vocabulary. A term's posting
+//! holds, per recipe, the sum over fields of field weight × term frequency.
+
+use crate::{CanonicalData, Recipe};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Searched fields and their weights, matching the order of importance of the old
+/// client-side scoring.
+pub(crate) const FIELDS: [(&str, u32); 6] = [
+    ("name", 3),
+    ("description", 2),
+    ("cuisine", 2),
+    ("meal_type", 1),
+    ("ingredient", 1),
+    ("preparation", 1),
+];
+
+const STOPWORDS: [&str; 12] = ["a", "an", "and", "for", "in", "into", "of", "on", "or", "the", "to", "with"];
+
+/// Query words shorter than this only match whole terms, not term prefixes.
+const MIN_PREFIX: usize = 3;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SearchIndex {
+    /// Field weights the postings were computed with.
+    pub(crate) weights: BTreeMap<String, u32>,
+    /// Recipe ids, newest first; postings refer to recipes by position in this list.
+    pub(crate) recipes: Vec<String>,
+    /// Term → `[recipe position, weighted term frequency]`, by recipe position.
+    pub(crate) terms: BTreeMap<String, Vec<[u32; 2]>>,
+}
+
+/// Lowercased words of `text`, without stopwords, with plurals folded to the canonical singular.
+pub(crate) fn tokenise(text: &str, canonical: &CanonicalData) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !STOPWORDS.contains(word) && !word.chars().all(|c| c.is_ascii_digit()))
+        .map(|word| canonical.ingredients.get(word).cloned().unwrap_or_else(|| word.to_string()))
+        .collect()
+}
+
+impl SearchIndex {
+    /// Index `recipes`, which are already in `recipes.json` order.
+    pub(crate) fn build(recipes: &[Recipe], canonical: &CanonicalData) -> Self {
+        let mut terms: BTreeMap<String, Vec<[u32; 2]>> = BTreeMap::new();
+        for (position, recipe) in recipes.iter().enumerate() {
+            let ingredients = || recipe.ingredients.values().flatten();
+            let fields: [Vec<&str>; 6] = [
+                vec![&recipe.name],
+                vec![&recipe.description],
+                recipe.cuisine.iter().map(String::as_str).collect(),
+                recipe.meal_type.iter().map(String::as_str).collect(),
+                ingredients().filter_map(|i| i.canonical.as_deref()).collect(),
+                ingredients().filter_map(|i| i.preparation.as_deref()).collect(),
+            ];
+            let mut scores: HashMap<String, u32> = HashMap::new();
+            for ((_, weight), texts) in FIELDS.iter().zip(fields) {
+                for term in texts.into_iter().flat_map(|text| tokenise(text, canonical)) {
+                    *scores.entry(term).or_default() += weight;
+                }
+            }
+            for (term, score) in scores {
+                terms.entry(term).or_default().push([position as u32, score]);
+            }
+        }
+        for postings in terms.values_mut() {
+            postings.sort();
+        }
+        Self {
+            weights: FIELDS.iter().map(|(field, weight)| (field.to_string(), *weight)).collect(),
+            recipes: recipes.iter().map(|r| r.id.clone()).collect(),
+            terms,
+        }
+    }
+
+    /// Recipe ids matching `query` with their scores, best first. Recipes matching more query
+    /// words rank above those matching fewer; then by score, then newest first. A word with no
+    /// exact term matches every term it prefixes, so partial words still find recipes.
+    pub(crate) fn search(&self, query: &str, canonical: &CanonicalData) -> Vec<(&str, u32)> {
+        // Per recipe position: (query words matched, score)
+        let mut hits: BTreeMap<u32, (usize, u32)> = BTreeMap::new();
+        for word in tokenise(query, canonical) {
+            let mut scores: HashMap<u32, u32> = HashMap::new();
+            let postings: Vec<&Vec<[u32; 2]>> = match self.terms.get(&word) {
+                Some(postings) => vec![postings],
+                None if word.chars().count() >= MIN_PREFIX => self.terms.range(word.clone()..)
+                    .take_while(|(term, _)| term.starts_with(&word))
+                    .map(|(_, postings)| postings)
+                    .collect(),
+                None => Vec::new(),
+            };
+            for [position, score] in postings.into_iter().flatten() {
+                *scores.entry(*position).or_default() += score;
+            }
+            for (position, score) in scores {
+                let hit = hits.entry(position).or_default();
+                hit.0 += 1;
+                hit.1 += score;
+            }
+        }
+        let mut ranked: Vec<(u32, (usize, u32))> = hits.into_iter().collect();
+        ranked.sort_by(|(a, (a_words, a_score)), (b, (b_words, b_score))| {
+            b_words.cmp(a_words).then(b_score.cmp(a_score)).then(a.cmp(b))
+        });
+        ranked.into_iter().map(|(position, (_, score))| (self.recipes[position as usize].as_str(), score)).collect()
+    }
+}
+
+/// The `search` subcommand: rank recipes in the built `index` for `query`.
+pub(crate) fn run(index: &Path, query: &str, limit: usize, canonical: &CanonicalData) -> Result<()> {
+    let contents = std::fs::read_to_string(index)
+        .with_context(|| format!("Failed to read search index {:?}; run the parser to build it", index))?;
+    let index: SearchIndex = serde_json::from_str(&contents).context("Failed to parse search index")?;
+    let results = index.search(query, canonical);
+    if results.is_empty() {
+        println!("No recipes match {:?}", query);
+    }
+    for (id, score) in results.into_iter().take(limit) {
+        println!("{:>4}  {}", score, id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{vocabulary, TestRecipe};
+    use crate::{load_canonical, parse_all, recipe_paths, VOCABULARY_PATH};
+
+    fn canonical() -> CanonicalData {
+        vocabulary(&[("lentil", "Pantry"), ("tomato", "Fresh")], &[("lentils", "lentil"), ("tomatoes", "tomato")])
+    }
+
+    fn recipe(id: &str, name: &str, description: &str, cuisine: &str, ingredients: &str) -> Recipe {
+        TestRecipe::new(id).set("name", name).set("description", description).set("cuisine", format!("[{}]", cuisine))
+            .ingredients(ingredients).parse(&canonical())
+    }
+
+    #[test]
+    fn test_tokenise_folds_plurals_and_drops_stopwords() {
+        assert_eq!(tokenise("Lentils with 400 g Tomatoes, and rice-noodles", &canonical()), vec!["lentil", "g", "tomato", "rice", "noodles"]);
+    }
+
+    #[test]
+    fn test_search_weights_fields() {
+        let recipes = vec![
+            recipe("soup", "Tomato Soup", "A quick soup", "french", "- 4 [tomatoes], chopped"),
+            recipe("dal", "Red Dal", "Lentils simmered with tomatoes", "indian", "- 200 g red [lentils]\n- 2 [tomatoes]"),
+            recipe("stew", "Lentil Stew", "A hearty stew", "french", "- 200 g [lentils]"),
+        ];
+        let canonical = canonical();
+        let index = SearchIndex::build(&recipes, &canonical);
+        // Name (3) + ingredient (1) beats description (2) + ingredient (1)
+        assert_eq!(index.terms["lentil"], vec![[1, 3], [2, 4]]);
+        assert_eq!(index.search("lentils", &canonical), vec![("stew", 4), ("dal", 3)]);
+        // Recipes matching both words come first, whatever their score
+        assert_eq!(index.search("tomato lentil", &canonical), vec![("dal", 6), ("soup", 4), ("stew", 4)]);
+        // Prefixes match while typing, but not from one or two letters
+        assert_eq!(index.search("ind", &canonical), vec![("dal", 2)]);
+        assert!(index.search("in", &canonical).is_empty());
+        assert_eq!(index.search("chopped", &canonical), vec![("soup", 1)]);
+    }
+
+    #[test]
+    fn test_search_real_recipes() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let canonical = load_canonical(&root.join(VOCABULARY_PATH)).unwrap();
+        let paths = recipe_paths(&root.join("recipes")).unwrap();
+        let recipes: Vec<_> = parse_all(&paths, false, &canonical, None).into_iter().filter_map(|(recipe, _, _)| recipe).collect();
+        let index = SearchIndex::build(&recipes, &canonical);
+
+        let results = index.search("chickpeas", &canonical);
+        assert!(!results.is_empty());
+        assert_eq!(results, index.search("chickpea", &canonical));
+    }
+}
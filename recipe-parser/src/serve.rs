@@ -0,0 +1,271 @@
+//! `serve`: a local preview server for recipe authors. Serves `docs/` on localhost, rebuilds
+//! on change like `watch`, and tells open pages to reload over server-sent events. While a
+//! recipe has errors the last good build stays in place and pages show the errors in an overlay.
+//!
+//! Deliberately minimal HTTP/1.1 on std: GET/HEAD of static files, one connection per request.
+
+use crate::diagnostics::{self, Format};
+use crate::watch::{self, Workspace, POLL_INTERVAL};
+use crate::Layout;
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+/// Server-sent events endpoint the injected script listens on.
+const EVENTS_PATH: &str = "/__livereload";
+
+/// Injected before `</body>` of every HTML page: reload on rebuild, overlay on build errors.
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+(() => {
+  const show = (text) => {
+    let overlay = document.getElementById('__recipe_errors');
+    if (!text) { if (overlay) overlay.remove(); return; }
+    if (!overlay) {
+      overlay = document.createElement('pre');
+      overlay.id = '__recipe_errors';
+      overlay.style.cssText = 'position:fixed;inset:0;margin:0;padding:24px;overflow:auto;z-index:2147483647;' +
+        'background:rgba(24,24,24,.96);color:#f3b0b0;font:13px/1.5 ui-monospace,monospace;white-space:pre-wrap';
+      document.body.appendChild(overlay);
+    }
+    overlay.textContent = 'Recipe build failed — fix the errors below and save to rebuild.\n\n' + text;
+  };
+  const events = new EventSource('/__livereload');
+  events.addEventListener('reload', () => location.reload());
+  events.addEventListener('errors', (e) => show(JSON.parse(e.data)));
+})();
+</script>
+"#;
+
+/// Build errors shown to pages, and the event streams of pages currently open.
+#[derive(Default)]
+struct State {
+    errors: Mutex<String>,
+    clients: Mutex<Vec<TcpStream>>,
+}
+
+impl State {
+    /// Send an event to every open page, forgetting pages that have gone away.
+    fn broadcast(&self, event: &str, data: &str) {
+        let message = sse_message(event, data);
+        let mut clients = self.clients.lock().expect("clients lock");
+        clients.retain_mut(|stream| stream.write_all(message.as_bytes()).and_then(|_| stream.flush()).is_ok());
+    }
+}
+
+fn sse_message(event: &str, data: &str) -> String {
+    // Data is JSON-encoded so it always fits on one `data:` line
+    format!("event: {}\ndata: {}\n\n", event, serde_json::to_string(data).expect("string serializes"))
+}
+
+/// Rebuild the outputs, unless a recipe has errors: then keep the last good build and
+/// return the errors for the overlay.
+fn build(workspace: &Workspace) -> String {
+    if workspace.has_errors() {
+        return diagnostics::render_errors(&workspace.all_reports());
+    }
+    match workspace.write() {
+        Ok(_) => String::new(),
+        Err(e) => format!("{:#}", e),
+    }
+}
+
+pub(crate) fn run(input: &Path, output: &Path, vocabulary: &Path, lint: bool, layout: Layout, imperial: bool, port: u16) -> Result<()> {
+    let root = output.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+    let mut workspace = Workspace::open(input, output, vocabulary, lint, layout, imperial)?;
+    diagnostics::print_reports(&workspace.all_reports(), Format::Text);
+
+    let state = Arc::new(State::default());
+    *state.errors.lock().expect("errors lock") = build(&workspace);
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .with_context(|| format!("Failed to listen on port {}", port))?;
+    println!("\n🌐 Serving {:?} at http://localhost:{}", root, port);
+    println!("👀 Watching {:?} and {:?} for changes (Ctrl+C to stop)", input, vocabulary);
+
+    let server_state = Arc::clone(&state);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (root, state) = (root.clone(), Arc::clone(&server_state));
+            thread::spawn(move || {
+                if let Err(e) = handle(stream, &root, &state) {
+                    eprintln!("⚠️  Request failed: {:#}", e);
+                }
+            });
+        }
+    });
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let started = Instant::now();
+        if watch::poll(&mut workspace).is_none() {
+            continue;
+        }
+        let errors = build(&workspace);
+        *state.errors.lock().expect("errors lock") = errors.clone();
+        if errors.is_empty() {
+            println!("🔄 Rebuilt in {} ms; reloading open pages", started.elapsed().as_millis());
+            state.broadcast("reload", "");
+        } else {
+            eprintln!("❌ Build failed; showing errors in open pages");
+            state.broadcast("errors", &errors);
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, root: &Path, state: &State) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers; nothing in them matters here
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    if method != "GET" && method != "HEAD" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"Method not allowed", true);
+    }
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+
+    if path == EVENTS_PATH {
+        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n")?;
+        let errors = state.errors.lock().expect("errors lock").clone();
+        stream.write_all(sse_message("errors", &errors).as_bytes())?;
+        state.clients.lock().expect("clients lock").push(stream);
+        return Ok(());
+    }
+
+    let head = method == "HEAD";
+    let Some(file) = resolve(root, path) else {
+        return respond(&mut stream, "404 Not Found", "text/plain", b"Not found", head);
+    };
+    let mut body = std::fs::read(&file)?;
+    let content_type = content_type(&file);
+    if content_type.starts_with("text/html") {
+        body = inject_script(&body);
+    }
+    respond(&mut stream, "200 OK", content_type, &body, head)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8], head: bool) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status, content_type, body.len()
+    )?;
+    if !head {
+        stream.write_all(body)?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Map a URL path onto a file under `root`. Directories serve their `index.html`; anything
+/// escaping `root` is refused.
+fn resolve(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(url_path)?;
+    let relative = Path::new(decoded.trim_start_matches('/'));
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let mut file = root.join(relative);
+    if file.is_dir() {
+        file = file.join("index.html");
+    }
+    file.is_file().then_some(file)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or_default() {
+        "html" => "text/html; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "ico" => "image/x-icon",
+        "mp4" => "video/mp4",
+        "woff2" => "font/woff2",
+        "txt" | "" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn inject_script(html: &[u8]) -> Vec<u8> {
+    let html = String::from_utf8_lossy(html);
+    match html.rfind("</body>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], LIVE_RELOAD_SCRIPT, &html[pos..]).into_bytes(),
+        None => format!("{}{}", html, LIVE_RELOAD_SCRIPT).into_bytes(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_resolve_stays_inside_root() {
+        let root = std::env::temp_dir().join(format!("recipe-serve-{}", std::process::id()));
+        fs::create_dir_all(root.join("r")).unwrap();
+        fs::write(root.join("index.html"), "<html></html>").unwrap();
+        fs::write(root.join("r/dal tadka.html"), "<html></html>").unwrap();
+
+        assert_eq!(resolve(&root, "/"), Some(root.join("index.html")));
+        assert_eq!(resolve(&root, "/index.html"), Some(root.join("index.html")));
+        assert_eq!(resolve(&root, "/r/dal%20tadka.html"), Some(root.join("r/dal tadka.html")));
+        assert_eq!(resolve(&root, "/missing.html"), None);
+        assert_eq!(resolve(&root, "/../Cargo.toml"), None);
+        assert_eq!(resolve(&root, "/r/%2e%2e/%2e%2e/etc/passwd"), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_injects_live_reload_before_body_end() {
+        let html = inject_script(b"<html><body><p>Hi</p></body></html>");
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.starts_with("<html><body><p>Hi</p><script>"));
+        assert!(html.ends_with("</script>\n</body></html>"));
+        assert!(html.contains(EVENTS_PATH));
+    }
+
+    #[test]
+    fn test_sse_message_is_single_line_data() {
+        assert_eq!(sse_message("errors", "line one\nline \"two\""), "event: errors\ndata: \"line one\\nline \\\"two\\\"\"\n\n");
+        assert_eq!(sse_message("reload", ""), "event: reload\ndata: \"\"\n\n");
+    }
+
+    #[test]
+    fn test_content_types() {
+        assert_eq!(content_type(Path::new("docs/recipes.json")), "application/json");
+        assert_eq!(content_type(Path::new("docs/index.html")), "text/html; charset=utf-8");
+        assert_eq!(content_type(Path::new("docs/CNAME")), "text/plain; charset=utf-8");
+    }
+}
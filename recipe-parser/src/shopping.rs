@@ -0,0 +1,431 @@
+//! Shopping lists: the ingredients of several recipes, each scaled to the servings wanted,
+//! merged by canonical name and grouped by shopping section in `VALID_CATEGORIES` order.
+//!
+//! Mirrors the merged view of `docs/js/shopping.js`: quantities of the same item are summed
+//! when their units match (ranges and secondary quantities too), other units are listed side
+//! by side, and items without a scalable quantity are listed once. Unlike the page, mass and
+//! volume units convert through the vocabulary, so "500 ml" and "1 l" sum to "1500 ml".
+
+use crate::{parse_all, recipe_paths, CanonicalData, Ingredient, Recipe, VALID_CATEGORIES};
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::path::Path;
+
+/// One recipe's line for an item, scaled and without its preparation ("3 cloves garlic").
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Line {
+    pub(crate) recipe: String,
+    pub(crate) text: String,
+}
+
+/// The summed quantity of an item in one unit.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Quantity {
+    pub(crate) amount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) amount_max: Option<f64>,
+    /// Singular form
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) secondary_amount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) secondary_unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) prefix: Option<String>,
+    #[serde(skip)]
+    item: String,
+    #[serde(skip)]
+    sources: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Item {
+    pub(crate) canonical: String,
+    /// What to buy, e.g. "7 cloves garlic" or "lentils (250 g + 1 tin)"
+    pub(crate) text: String,
+    /// Summed quantities, one per unit; empty when no recipe has a scalable quantity
+    pub(crate) quantities: Vec<Quantity>,
+    pub(crate) lines: Vec<Line>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Section {
+    pub(crate) section: String,
+    pub(crate) items: Vec<Item>,
+}
+
+/// Round a scaled amount to what a cook would measure: metric to the gram (nearest 5 above
+/// 50), spoons to the quarter, countable units to the half, anything else to 2 decimals.
+pub(crate) fn smart_round(n: f64, unit: Option<&str>, canonical: &CanonicalData) -> f64 {
+    if n <= 0.0 {
+        return 0.0;
+    }
+    let unit = unit.unwrap_or("").to_lowercase();
+    match unit.as_str() {
+        "g" | "ml" if n > 50.0 => (n / 5.0).round() * 5.0,
+        "g" | "ml" => n.round(),
+        "kg" | "l" => smart_round(n * 1000.0, Some("g"), canonical) / 1000.0,
+        "tsp" | "tbsp" => (n * 4.0).round() / 4.0,
+        _ if canonical.unit_plurals.contains_key(canonical.unit_singular(&unit)) => (n * 2.0).round() / 2.0,
+        _ => (n * 100.0).round() / 100.0,
+    }
+}
+
+/// "1 1/2", "1/3", "2" or "0.4".
+pub(crate) fn format_amount(n: f64) -> String {
+    const FRACTIONS: [(f64, &str); 5] = [(0.25, "1/4"), (1.0 / 3.0, "1/3"), (0.5, "1/2"), (2.0 / 3.0, "2/3"), (0.75, "3/4")];
+    if n <= 0.0 {
+        return "0".to_string();
+    }
+    let whole = n.floor();
+    let frac = n - whole;
+    if let Some((_, fraction)) = FRACTIONS.iter().find(|(value, _)| (frac - value).abs() < 0.05) {
+        return if whole > 0.0 { format!("{} {}", whole, fraction) } else { fraction.to_string() };
+    }
+    if frac < 0.05 {
+        return format!("{}", whole);
+    }
+    format!("{}", (n * 10.0).round() / 10.0)
+}
+
+fn capitalise_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The ingredient text without its trailing preparation, which doesn't matter when shopping.
+pub(crate) fn without_preparation(ingredient: &Ingredient) -> &str {
+    match &ingredient.preparation {
+        Some(preparation) => ingredient.text.strip_suffix(preparation.as_str())
+            .map_or(ingredient.text.as_str(), |text| text.trim_end_matches([',', ' '])),
+        None => &ingredient.text,
+    }
+}
+
+/// The ingredient line scaled by `ratio`, without preparation.
+pub(crate) fn scaled_text(ingredient: &Ingredient, ratio: f64, canonical: &CanonicalData) -> String {
+    let Some(q) = ingredient.quantity.as_ref().filter(|_| ratio != 1.0) else {
+        return capitalise_first(without_preparation(ingredient));
+    };
+    let mut text = String::new();
+    if let Some(prefix) = &q.prefix {
+        text += &format!("{} ", prefix);
+    }
+    text += &format_amount(smart_round(q.amount * ratio, q.unit.as_deref(), canonical));
+    if let Some(max) = q.amount_max {
+        text += &format!("-{}", format_amount(smart_round(max * ratio, q.unit.as_deref(), canonical)));
+    }
+    if let Some(unit) = &q.unit {
+        text += &format!(" {}", unit);
+    }
+    if let Some(secondary) = q.secondary_amount {
+        let prefix = q.secondary_prefix.as_ref().map(|p| format!("{} ", p)).unwrap_or_default();
+        let unit = q.secondary_unit.as_ref().map(|u| format!(" {}", u)).unwrap_or_default();
+        text += &format!(" ({}{}{})", prefix, format_amount(smart_round(secondary * ratio, q.secondary_unit.as_deref(), canonical)), unit);
+    }
+    if !q.item.is_empty() {
+        text += &format!(" {}", q.item);
+    }
+    capitalise_first(&text)
+}
+
+/// The unit to sum amounts of `a` and `b` in, if they convert: the smaller of the two, so the
+/// sum stays in whole grams, millilitres or spoons.
+fn common_unit<'a>(a: &'a str, b: &'a str, canonical: &CanonicalData) -> Option<&'a str> {
+    let ratio = canonical.convert_unit(1.0, a, b)?;
+    Some(if ratio > 1.0 { b } else { a })
+}
+
+/// Whether amounts in `a` and `b` can be summed.
+fn units_convert(a: Option<&str>, b: Option<&str>, canonical: &CanonicalData) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => canonical.convert_unit(1.0, a, b).is_some(),
+        _ => false,
+    }
+}
+
+impl Quantity {
+    /// "Juice of 1 1/2", "3-4 cloves", "2 tins (800 g)".
+    fn label(&self, canonical: &CanonicalData) -> String {
+        let mut label = self.prefix.as_ref().map(|p| format!("{} ", p)).unwrap_or_default();
+        label += &format_amount(self.amount);
+        if let Some(max) = self.amount_max {
+            label += &format!("-{}", format_amount(max));
+        }
+        if let Some(unit) = &self.unit {
+            label += &format!(" {}", canonical.unit_for_amount(unit, self.amount_max.unwrap_or(self.amount)));
+        }
+        if let Some(secondary) = self.secondary_amount {
+            let unit = self.secondary_unit.as_ref().map(|u| format!(" {}", u)).unwrap_or_default();
+            label += &format!(" ({}{})", format_amount(secondary), unit);
+        }
+        label
+    }
+
+    /// Express the amounts in `unit`, which the current unit converts to.
+    fn convert(&mut self, unit: &str, canonical: &CanonicalData) {
+        let Some(from) = self.unit.as_deref() else { return };
+        let ratio = canonical.convert_unit(1.0, from, unit).unwrap_or(1.0);
+        self.amount *= ratio;
+        self.amount_max = self.amount_max.map(|max| max * ratio);
+        self.unit = Some(unit.to_string());
+    }
+
+    /// Add another recipe's scaled quantity in a unit that converts to this one.
+    fn add(&mut self, mut other: Quantity, canonical: &CanonicalData) {
+        if let (Some(a), Some(b)) = (&self.unit, &other.unit) {
+            if let Some(unit) = common_unit(a, b, canonical).map(str::to_string) {
+                self.convert(&unit, canonical);
+                other.convert(&unit, canonical);
+            }
+        }
+        let unit = self.unit.clone();
+        let round = |n: f64| smart_round(n, unit.as_deref(), canonical);
+        self.amount_max = match (self.amount_max, other.amount_max) {
+            (None, None) => None,
+            (max, other_max) => Some(round(max.unwrap_or(self.amount) + other_max.unwrap_or(other.amount))),
+        };
+        self.amount = round(self.amount + other.amount);
+        let secondary = match (self.secondary_amount, other.secondary_amount, self.secondary_unit.as_deref(), other.secondary_unit.as_deref()) {
+            (Some(a), Some(b), None, None) => Some((a + b, None)),
+            (Some(a), Some(b), Some(a_unit), Some(b_unit)) => common_unit(a_unit, b_unit, canonical).map(|unit| (
+                canonical.convert_unit(a, a_unit, unit).unwrap_or(a) + canonical.convert_unit(b, b_unit, unit).unwrap_or(b),
+                Some(unit.to_string()),
+            )),
+            _ => None,
+        };
+        (self.secondary_amount, self.secondary_unit) = match secondary {
+            Some((sum, unit)) => (Some(smart_round(sum, unit.as_deref(), canonical)), unit),
+            None => (None, None),
+        };
+        self.sources += 1;
+    }
+}
+
+/// The merged text of an item: one summed quantity reads as an ingredient line, several are
+/// listed after the item name.
+fn item_text(quantities: &[Quantity], lines: &[Line], canonical: &CanonicalData) -> String {
+    match quantities {
+        // Nothing scalable: list the first recipe's line once
+        [] => lines[0].text.clone(),
+        // A single unitless line keeps its text, where the parser may have dropped the unit
+        [q] if q.sources == 1 && q.unit.is_none() => lines[0].text.clone(),
+        [q] => {
+            // "2 lemons", but "2 tins chopped tomatoes" as written
+            let item = match q.unit {
+                Some(_) => q.item.as_str(),
+                None => canonical.ingredient_for_amount(&q.item, q.amount_max.unwrap_or(q.amount)),
+            };
+            capitalise_first(format!("{} {}", q.label(canonical), item).trim_end())
+        }
+        [first, ..] => {
+            let labels: Vec<String> = quantities.iter().map(|q| q.label(canonical)).collect();
+            format!("{} ({})", capitalise_first(&first.item), labels.join(" + "))
+        }
+    }
+}
+
+/// Merge the ingredients of `recipes`, each scaled by its ratio: one item per canonical name
+/// (or lowercased text, for ingredients outside the vocabulary), in the section and order it
+/// first appears in.
+pub(crate) fn merge(recipes: &[(&Recipe, f64)], canonical: &CanonicalData) -> Vec<Section> {
+    // (section, canonical, quantities, lines), in first-seen order
+    let mut items: Vec<(&str, String, Vec<Quantity>, Vec<Line>)> = Vec::new();
+    for &(recipe, ratio) in recipes {
+        for &category in VALID_CATEGORIES {
+            for ingredient in recipe.ingredients.get(category).into_iter().flatten() {
+                let key = ingredient.canonical.clone().unwrap_or_else(|| ingredient.text.to_lowercase());
+                let position = match items.iter().position(|(_, k, _, _)| *k == key) {
+                    Some(position) => position,
+                    None => {
+                        items.push((category, key, Vec::new(), Vec::new()));
+                        items.len() - 1
+                    }
+                };
+                let (_, _, quantities, lines) = &mut items[position];
+                lines.push(Line { recipe: recipe.id.clone(), text: scaled_text(ingredient, ratio, canonical) });
+
+                let Some(q) = &ingredient.quantity else { continue };
+                let unit = q.unit.as_deref().map(|u| canonical.unit_singular(u).to_string());
+                let scale = |n: f64, unit: Option<&str>| smart_round(n * ratio, unit, canonical);
+                let scaled = Quantity {
+                    amount: scale(q.amount, unit.as_deref()),
+                    amount_max: q.amount_max.map(|max| scale(max, unit.as_deref())),
+                    secondary_amount: q.secondary_amount.map(|a| scale(a, q.secondary_unit.as_deref())),
+                    secondary_unit: q.secondary_unit.clone(),
+                    prefix: q.prefix.clone(),
+                    item: q.item.clone(),
+                    sources: 1,
+                    unit,
+                };
+                let matching = quantities.iter_mut()
+                    .find(|e| e.prefix == scaled.prefix && units_convert(e.unit.as_deref(), scaled.unit.as_deref(), canonical));
+                match matching {
+                    Some(existing) => existing.add(scaled, canonical),
+                    None => quantities.push(scaled),
+                }
+            }
+        }
+    }
+
+    let mut sections: Vec<Section> = Vec::new();
+    for &category in VALID_CATEGORIES {
+        let section_items: Vec<Item> = items.iter_mut()
+            .filter(|(section, _, _, _)| *section == category)
+            .map(|(_, key, quantities, lines)| Item {
+                canonical: key.clone(),
+                text: item_text(quantities, lines, canonical),
+                quantities: std::mem::take(quantities),
+                lines: std::mem::take(lines),
+            })
+            .collect();
+        if !section_items.is_empty() {
+            sections.push(Section { section: category.to_string(), items: section_items });
+        }
+    }
+    sections
+}
+
+/// A `recipe-id` or `recipe-id:servings` argument.
+fn parse_request<'a>(arg: &str, recipes: &'a [Recipe]) -> Result<(&'a Recipe, f64)> {
+    let (id, servings) = match arg.split_once(':') {
+        Some((id, servings)) => {
+            let servings: u32 = servings.parse().ok().filter(|s| *s > 0)
+                .ok_or_else(|| anyhow!("Invalid servings in '{}': expected a whole number above 0", arg))?;
+            (id, Some(servings))
+        }
+        None => (arg, None),
+    };
+    let recipe = recipes.iter().find(|r| r.id == id).ok_or_else(|| anyhow!("Unknown recipe '{}'", id))?;
+    Ok((recipe, servings.map_or(1.0, |s| s as f64 / recipe.servings as f64)))
+}
+
+/// The `shop` subcommand: a merged shopping list for `requests` ("dal-tadka:6").
+pub(crate) fn run(requests: &[String], json: bool, input: &Path, canonical: &CanonicalData) -> Result<()> {
+    let paths = recipe_paths(input)?;
+    let recipes: Vec<Recipe> = parse_all(&paths, false, canonical, None).into_iter().filter_map(|(recipe, _, _)| recipe).collect();
+    let mut wanted: Vec<(&Recipe, f64)> = Vec::new();
+    for request in requests {
+        let (recipe, ratio) = parse_request(request, &recipes)?;
+        if wanted.iter().any(|(r, _)| r.id == recipe.id) {
+            bail!("'{}' is listed twice", recipe.id);
+        }
+        wanted.push((recipe, ratio));
+    }
+    let sections = merge(&wanted, canonical);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&sections)?);
+        return Ok(());
+    }
+    let names: Vec<String> = wanted.iter()
+        .map(|(r, ratio)| format!("{} ×{}", r.name, (r.servings as f64 * ratio).round()))
+        .collect();
+    println!("🛒 Shopping list for {}", names.join(", "));
+    for section in &sections {
+        println!("  {}", section.section);
+        for item in &section.items {
+            println!("    - {}", item.text);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{vocabulary, TestRecipe};
+
+    fn canonical() -> CanonicalData {
+        vocabulary(
+            &[("garlic", "Fresh"), ("chickpea", "Pantry"), ("olive oil", "Condiments"), ("egg", "Fridge"),
+                ("chopped tomato", "Pantry"), ("lemon", "Fresh"), ("salt", "Spices")],
+            &[("chickpeas", "chickpea"), ("eggs", "egg"), ("chopped tomatoes", "chopped tomato"), ("lemons", "lemon")],
+        )
+    }
+
+    fn recipe(id: &str, servings: u32, ingredients: &str) -> Recipe {
+        TestRecipe::new(id).set("servings", servings).ingredients(ingredients).parse(&canonical())
+    }
+
+    fn texts(sections: &[Section]) -> Vec<(&str, Vec<&str>)> {
+        sections.iter().map(|s| (s.section.as_str(), s.items.iter().map(|i| i.text.as_str()).collect())).collect()
+    }
+
+    #[test]
+    fn test_merge_sums_matching_units() {
+        let hummus = recipe("hummus", 2, "- 1 tin (400 g) [chickpeas], drained\n- 2 cloves [garlic], crushed\n- 2 tbsp [olive oil]\n- Juice of 1/2 [lemon]\n- [Salt] to taste");
+        let stew = recipe("stew", 4, "- 3-4 cloves [garlic]\n- 1 tin (400 g) [chickpeas]\n- 200 g [chickpeas]\n- 1 [egg]\n- Juice of 1 [lemon]\n- [Salt] to taste");
+        let canonical = canonical();
+
+        // Hummus doubled, stew as written
+        let sections = merge(&[(&hummus, 2.0), (&stew, 1.0)], &canonical);
+        assert_eq!(texts(&sections), vec![
+            ("Fresh", vec!["7-8 cloves garlic", "Juice of 2 lemons"]),
+            ("Fridge", vec!["1 egg"]),
+            ("Pantry", vec!["Chickpeas (3 tins (1200 g) + 200 g)"]),
+            ("Condiments", vec!["4 tbsp olive oil"]),
+            ("Spices", vec!["Salt"]),
+        ]);
+        let garlic = &sections[0].items[0];
+        assert_eq!(garlic.quantities[0].amount, 7.0);
+        assert_eq!(garlic.quantities[0].amount_max, Some(8.0));
+        assert_eq!(garlic.quantities[0].unit.as_deref(), Some("clove"));
+        assert_eq!(garlic.lines, vec![
+            Line { recipe: "hummus".into(), text: "4 cloves garlic".into() },
+            Line { recipe: "stew".into(), text: "3-4 cloves garlic".into() },
+        ]);
+        let salt = &sections[4].items[0];
+        assert!(salt.quantities.is_empty());
+        assert_eq!(salt.lines.len(), 2);
+    }
+
+    #[test]
+    fn test_secondary_dropped_when_units_differ() {
+        let a = recipe("a", 2, "- 1 tin (400 g) [chopped tomatoes]");
+        let b = recipe("b", 2, "- 1 tin [chopped tomatoes]");
+        let sections = merge(&[(&a, 1.0), (&b, 1.0)], &canonical());
+        assert_eq!(sections[0].items[0].text, "2 tins chopped tomatoes");
+    }
+
+    #[test]
+    fn test_merge_converts_mass_and_volume() {
+        let a = recipe("a", 2, "- 500 ml [olive oil]\n- 1 tbsp [salt]\n- 1 tin (400 ml) [chopped tomatoes]");
+        let b = recipe("b", 2, "- 1 l [olive oil]\n- 3 tsp [salt]\n- 2 tins (0.5 l) [chopped tomatoes]");
+        let c = recipe("c", 2, "- 100 g [olive oil]");
+        let sections = merge(&[(&a, 1.0), (&b, 1.0), (&c, 1.0)], &canonical());
+        assert_eq!(texts(&sections), vec![
+            ("Pantry", vec!["3 tins (900 ml) chopped tomatoes"]),
+            ("Condiments", vec!["Olive oil (1500 ml + 100 g)"]),
+            ("Spices", vec!["6 tsp salt"]),
+        ]);
+    }
+
+    #[test]
+    fn test_rounding_and_formatting() {
+        let canonical = canonical();
+        assert_eq!(smart_round(133.0, Some("g"), &canonical), 135.0);
+        assert_eq!(smart_round(0.5, Some("l"), &canonical), 0.5);
+        assert_eq!(smart_round(1.6, Some("tbsp"), &canonical), 1.5);
+        assert_eq!(smart_round(2.3, Some("cloves"), &canonical), 2.5);
+        assert_eq!(smart_round(0.333333, None, &canonical), 0.33);
+        assert_eq!(format_amount(1.5), "1 1/2");
+        assert_eq!(format_amount(0.33), "1/3");
+        assert_eq!(format_amount(2.0), "2");
+        assert_eq!(format_amount(0.4), "0.4");
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let recipes = vec![recipe("dal", 4, "- 2 cloves [garlic]")];
+        assert_eq!(parse_request("dal:6", &recipes).unwrap().1, 1.5);
+        assert_eq!(parse_request("dal", &recipes).unwrap().1, 1.0);
+        assert!(parse_request("dal:0", &recipes).is_err());
+        assert!(parse_request("soup:2", &recipes).unwrap_err().to_string().contains("Unknown recipe"));
+    }
+}
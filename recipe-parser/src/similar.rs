@@ -0,0 +1,162 @@
+//! "Similar recipes", precomputed at build time into `similar-recipes.json` so recipe pages
+//! don't score the whole corpus on every view.
+//!
+//! Two recipes are similar when they share identity-defining ingredients: spices and pantry
+//! staples are ignored, rare ingredients count more (IDF), and perishables more than
+//! condiments, more than pantry goods. Mirrors the fallback in `docs/js/recommendations.js`.
+
+use crate::{CanonicalData, Recipe, VALID_CATEGORIES};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Recommendations kept per recipe. Pages show fewer, after dropping the recipes the user's
+/// settings hide.
+pub(crate) const TOP_N: usize = 10;
+
+/// Staples that don't meaningfully define a recipe's identity.
+const PANTRY_STOPLIST: [&str; 10] = [
+    "water", "vegetable stock", "plain flour", "self-raising flour",
+    "baking soda", "baking powder", "maple syrup",
+    "butter", "vegan butter", "margarine",
+];
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Similar {
+    pub(crate) id: String,
+    /// Sum of IDF × section weight over the shared ingredients, rounded to 2 decimals.
+    pub(crate) score: f64,
+    pub(crate) shared: Vec<String>,
+}
+
+pub(crate) fn is_stop_ingredient(canonical: &str) -> bool {
+    PANTRY_STOPLIST.contains(&canonical)
+        || ["salt", "sugar", "black pepper", "white pepper"].iter().any(|s| canonical.contains(s))
+        || canonical.ends_with(" oil")
+        || canonical.ends_with(" milk")
+        || canonical == "milk"
+}
+
+/// Perishable ingredients (Fresh/Fridge) are most identity-defining; condiments (tahini,
+/// miso, soy sauce) are more distinctive than generic pantry goods.
+fn section_weight(section: &str) -> f64 {
+    match section {
+        "Fresh" | "Fridge" => 2.0,
+        "Condiments" => 1.5,
+        _ => 1.0,
+    }
+}
+
+/// The recipe's scored ingredients in display order, with their shopping section.
+pub(crate) fn ingredient_sections(recipe: &Recipe, canonical: &CanonicalData) -> Vec<(String, String)> {
+    let mut ingredients: Vec<(String, String)> = Vec::new();
+    for &category in VALID_CATEGORIES {
+        // Spices don't define recipe identity
+        let Some(list) = recipe.ingredients.get(category).filter(|_| category != "Spices") else { continue };
+        for ingredient in list {
+            let name = ingredient.canonical.clone().unwrap_or_else(|| ingredient.text.to_lowercase());
+            if is_stop_ingredient(&name) || ingredients.iter().any(|(n, _)| *n == name) {
+                continue;
+            }
+            let section = canonical.ingredient_sections.get(&name).map_or(category, String::as_str);
+            ingredients.push((name, section.to_string()));
+        }
+    }
+    ingredients
+}
+
+/// ln(recipes / recipes using it) per ingredient of `maps`, one map per recipe.
+pub(crate) fn idf(maps: &[Vec<(String, String)>]) -> HashMap<String, f64> {
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for map in maps {
+        for (name, _) in map {
+            *df.entry(name).or_default() += 1;
+        }
+    }
+    df.into_iter().map(|(name, count)| (name.to_string(), (maps.len() as f64 / count as f64).ln())).collect()
+}
+
+/// Shared ingredients of `a` in its order, and the sum of their IDF × the higher of their
+/// two section weights.
+pub(crate) fn pair_score(a: &[(String, String)], b: &[(String, String)], idf: &HashMap<String, f64>) -> (f64, Vec<String>) {
+    let mut shared = Vec::new();
+    let mut score = 0.0;
+    for (name, section) in a {
+        if let Some((_, other_section)) = b.iter().find(|(n, _)| n == name) {
+            shared.push(name.clone());
+            score += idf[name] * section_weight(section).max(section_weight(other_section));
+        }
+    }
+    (score, shared)
+}
+
+/// Up to `TOP_N` similar recipes per recipe id, best first; recipes sharing nothing are left out.
+pub(crate) fn compute(recipes: &[Recipe], canonical: &CanonicalData) -> BTreeMap<String, Vec<Similar>> {
+    let maps: Vec<Vec<(String, String)>> = recipes.iter().map(|r| ingredient_sections(r, canonical)).collect();
+    let idf = idf(&maps);
+
+    let mut similar = BTreeMap::new();
+    for (target, target_map) in recipes.iter().zip(&maps) {
+        let mut scored: Vec<Similar> = Vec::new();
+        for (other, other_map) in recipes.iter().zip(&maps) {
+            if other.id == target.id {
+                continue;
+            }
+            let (score, shared) = pair_score(target_map, other_map, &idf);
+            if score > 0.0 {
+                scored.push(Similar { id: other.id.clone(), score: (score * 100.0).round() / 100.0, shared });
+            }
+        }
+        // Stable: equal scores keep recipes.json order
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(TOP_N);
+        similar.insert(target.id.clone(), scored);
+    }
+    similar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{vocabulary, TestRecipe};
+
+    fn canonical() -> CanonicalData {
+        vocabulary(&[("garlic", "Fresh"), ("lentil", "Pantry"), ("tahini", "Condiments"), ("cumin", "Spices"),
+            ("olive oil", "Condiments"), ("salt", "Spices"), ("rice", "Pantry"), ("butter", "Fridge")], &[])
+    }
+
+    fn recipe(id: &str, ingredients: &[&str]) -> Recipe {
+        let lines: Vec<String> = ingredients.iter().map(|name| format!("- 1 [{}]", name)).collect();
+        TestRecipe::new(id).ingredients(&lines.join("\n")).parse(&canonical())
+    }
+
+    #[test]
+    fn test_stoplist() {
+        for name in ["water", "sea salt", "brown sugar", "olive oil", "oat milk", "milk", "butter"] {
+            assert!(is_stop_ingredient(name), "{}", name);
+        }
+        for name in ["garlic", "coconut cream", "milk chocolate"] {
+            assert!(!is_stop_ingredient(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_similar_recipes_weighting() {
+        let recipes = vec![
+            recipe("dal", &["garlic", "lentil", "cumin", "olive oil", "salt"]),
+            recipe("salad", &["garlic", "lentil", "tahini", "cumin", "olive oil"]),
+            recipe("hummus", &["garlic", "tahini", "butter"]),
+            recipe("pilaf", &["rice", "lentil"]),
+            recipe("toast", &["butter", "salt"]),
+        ];
+        let similar = compute(&recipes, &canonical());
+
+        // garlic: ln(5/3) × 2 (Fresh), lentil: ln(5/3) × 1 (Pantry); spices and staples don't count
+        let dal = &similar["dal"];
+        assert_eq!(dal[0], Similar { id: "salad".into(), score: 1.53, shared: vec!["garlic".into(), "lentil".into()] });
+        assert_eq!(dal.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["salad", "hummus", "pilaf"]);
+        // tahini is rarer than garlic, and a condiment
+        assert_eq!(similar["hummus"][0].id, "salad");
+        assert_eq!(similar["hummus"][0].shared, vec!["garlic", "tahini"]);
+        assert!(similar["toast"].is_empty());
+    }
+}
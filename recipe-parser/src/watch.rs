@@ -0,0 +1,336 @@
+//! `watch`: rebuild `recipes.json`, the manifest and the OG pages whenever a recipe or the
+//! vocabulary changes.
+//!
+//! Changes are found by polling modification times rather than through OS notifications,
+//! which needs no extra dependency and copes with editors that save by renaming a temp file.
+
+use crate::diagnostics::{self, FileReport, Format};
+use crate::{load_canonical, parse_all, recipe_paths, write_outputs, CanonicalData, Layout, Recipe};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Modification time and size; either changing marks the file as changed.
+type Stamp = (SystemTime, u64);
+
+/// The recipes directory, vocabulary and parse results, kept between rebuilds so only
+/// changed files are re-parsed.
+pub(crate) struct Workspace {
+    input: PathBuf,
+    output: PathBuf,
+    vocabulary: PathBuf,
+    lint: bool,
+    layout: Layout,
+    imperial: bool,
+    canonical: CanonicalData,
+    /// Parse result per recipe file, in path order.
+    files: BTreeMap<PathBuf, (Option<Recipe>, FileReport)>,
+    /// Last seen stamp of every watched file, the vocabulary included.
+    stamps: HashMap<PathBuf, Stamp>,
+}
+
+/// What a `refresh` re-parsed or dropped.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Changes {
+    pub(crate) parsed: Vec<PathBuf>,
+    pub(crate) removed: Vec<PathBuf>,
+    pub(crate) vocabulary: bool,
+}
+
+impl Changes {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.parsed.is_empty() && self.removed.is_empty() && !self.vocabulary
+    }
+}
+
+impl Workspace {
+    /// Load the vocabulary and parse every recipe in `input`.
+    pub(crate) fn open(input: &Path, output: &Path, vocabulary: &Path, lint: bool, layout: Layout, imperial: bool) -> Result<Self> {
+        let mut workspace = Self {
+            input: input.to_path_buf(),
+            output: output.to_path_buf(),
+            vocabulary: vocabulary.to_path_buf(),
+            lint,
+            layout,
+            imperial,
+            canonical: load_canonical(vocabulary)?,
+            files: BTreeMap::new(),
+            stamps: HashMap::new(),
+        };
+        workspace.stamps = workspace.scan()?;
+        let paths = recipe_paths(input)?;
+        for (path, (recipe, report, _)) in paths.iter().zip(parse_all(&paths, lint, &workspace.canonical, None)) {
+            workspace.files.insert(path.clone(), (recipe, report));
+        }
+        Ok(workspace)
+    }
+
+    fn scan(&self) -> Result<HashMap<PathBuf, Stamp>> {
+        let mut paths = recipe_paths(&self.input)?;
+        if self.vocabulary.exists() {
+            paths.push(self.vocabulary.clone());
+        }
+        let mut stamps = HashMap::new();
+        for path in paths {
+            // Files can disappear between listing and stat; the next poll picks that up
+            if let Ok(metadata) = fs::metadata(&path) {
+                stamps.insert(path, (metadata.modified()?, metadata.len()));
+            }
+        }
+        Ok(stamps)
+    }
+
+    /// Re-parse whatever changed since the last call. A vocabulary change re-parses everything.
+    pub(crate) fn refresh(&mut self) -> Result<Changes> {
+        let stamps = self.scan()?;
+        if stamps == self.stamps {
+            return Ok(Changes::default());
+        }
+
+        let mut changes = Changes {
+            vocabulary: stamps.get(&self.vocabulary) != self.stamps.get(&self.vocabulary),
+            ..Changes::default()
+        };
+        if changes.vocabulary {
+            match load_canonical(&self.vocabulary) {
+                Ok(canonical) => self.canonical = canonical,
+                Err(e) => {
+                    // Report a broken vocabulary once, keep building with the previous one
+                    if let Some(stamp) = stamps.get(&self.vocabulary) {
+                        self.stamps.insert(self.vocabulary.clone(), *stamp);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        let recipes: HashSet<&PathBuf> = stamps.keys().filter(|p| **p != self.vocabulary).collect();
+        changes.removed = self.files.keys().filter(|p| !recipes.contains(p)).cloned().collect();
+        for path in &changes.removed {
+            self.files.remove(path);
+        }
+        changes.parsed = recipes.into_iter()
+            .filter(|p| changes.vocabulary || stamps.get(*p) != self.stamps.get(*p))
+            .cloned()
+            .collect();
+        changes.parsed.sort();
+        for (path, (recipe, report, _)) in changes.parsed.iter().zip(parse_all(&changes.parsed, self.lint, &self.canonical, None)) {
+            self.files.insert(path.clone(), (recipe, report));
+        }
+
+        self.stamps = stamps;
+        Ok(changes)
+    }
+
+    /// Reports for the given files, for printing after a rebuild.
+    pub(crate) fn reports(&self, paths: &[PathBuf]) -> Vec<FileReport> {
+        paths.iter().filter_map(|p| self.files.get(p)).map(|(_, report)| report.clone()).collect()
+    }
+
+    /// Reports for every file, in path order.
+    pub(crate) fn all_reports(&self) -> Vec<FileReport> {
+        self.files.values().map(|(_, report)| report.clone()).collect()
+    }
+
+    /// Whether any recipe currently fails to parse.
+    pub(crate) fn has_errors(&self) -> bool {
+        self.files.values().any(|(_, report)| report.diagnostics.has_errors())
+    }
+
+    /// Every recipe that parsed, one per id; later files with a taken id are skipped
+    /// with a warning, as in a full build.
+    pub(crate) fn recipes(&self) -> Vec<Recipe> {
+        let mut seen: HashMap<&str, &PathBuf> = HashMap::new();
+        let mut recipes = Vec::new();
+        for (path, (recipe, _)) in &self.files {
+            let Some(recipe) = recipe else { continue };
+            if let Some(existing) = seen.get(recipe.id.as_str()) {
+                eprintln!("⚠️  Duplicate recipe ID '{}' found in {:?} and {:?}; skipping the latter", recipe.id, existing, path);
+                continue;
+            }
+            seen.insert(&recipe.id, path);
+            recipes.push(recipe.clone());
+        }
+        recipes
+    }
+
+    /// Write the recipe data in `layout`, the manifest, search index and recipe pages, but no deltas:
+    /// every save would patch from the last one. Returns the number of recipes written.
+    pub(crate) fn write(&self) -> Result<usize> {
+        let recipes = self.recipes();
+        let count = recipes.len();
+        if count == 0 {
+            eprintln!("⚠️  No valid recipes found in {:?}; outputs left unchanged", self.input);
+        } else {
+            write_outputs(recipes, &self.output, self.layout, self.imperial, false, &self.canonical)
+                .with_context(|| format!("Failed to write outputs for {:?}", self.output))?;
+        }
+        Ok(count)
+    }
+}
+
+/// Pick up changes, reporting what changed and the diagnostics of re-parsed files.
+/// Returns None when nothing changed.
+pub(crate) fn poll(workspace: &mut Workspace) -> Option<Changes> {
+    let changes = match workspace.refresh() {
+        Ok(changes) if changes.is_empty() => return None,
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("❌ {:#}", e);
+            return None;
+        }
+    };
+
+    println!();
+    if changes.vocabulary {
+        println!("📚 Vocabulary changed; re-parsed {} recipe(s)", changes.parsed.len());
+    } else {
+        for path in &changes.parsed {
+            println!("  📄 Changed: {}", path.display());
+        }
+    }
+    for path in &changes.removed {
+        println!("  🗑️  Removed: {}", path.display());
+    }
+    diagnostics::print_reports(&workspace.reports(&changes.parsed), Format::Text);
+    Some(changes)
+}
+
+/// Build once, then rebuild on every change until interrupted.
+pub(crate) fn run(input: &Path, output: &Path, vocabulary: &Path, lint: bool, layout: Layout, imperial: bool) -> Result<()> {
+    let mut workspace = Workspace::open(input, output, vocabulary, lint, layout, imperial)?;
+    diagnostics::print_reports(&workspace.all_reports(), Format::Text);
+    workspace.write()?;
+    println!("\n👀 Watching {:?} and {:?} for changes (Ctrl+C to stop)", input, vocabulary);
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let started = Instant::now();
+        if poll(&mut workspace).is_none() {
+            continue;
+        }
+        match workspace.write() {
+            Ok(count) => println!("🔄 Rebuilt {} recipe(s) in {} ms", count, started.elapsed().as_millis()),
+            Err(e) => eprintln!("❌ {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::TestRecipe;
+
+    fn recipe(id: &str, name: &str) -> String {
+        TestRecipe::new(id).set("name", name).steps("1. Fry the {onion}.").source()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("recipe-watch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("recipes")).unwrap();
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_refresh_reparses_only_changed_files() {
+        let dir = temp_dir("changed");
+        let (a, b) = (dir.join("recipes/a.md"), dir.join("recipes/b.md"));
+        fs::write(&a, recipe("recipe-a", "Recipe A")).unwrap();
+        fs::write(&b, recipe("recipe-b", "Recipe B")).unwrap();
+        let vocabulary = dir.join("docs/ingredients.json");
+        let mut workspace = Workspace::open(&dir.join("recipes"), &dir.join("docs/recipes.json"), &vocabulary, false, Layout::Single, false).unwrap();
+        assert_eq!(workspace.recipes().len(), 2);
+        assert!(workspace.refresh().unwrap().is_empty());
+
+        fs::write(&b, recipe("recipe-b", "Recipe B, renamed")).unwrap();
+        let changes = workspace.refresh().unwrap();
+        assert_eq!(changes, Changes { parsed: vec![b.clone()], ..Changes::default() });
+        assert!(workspace.recipes().iter().any(|r| r.name == "Recipe B, renamed"));
+
+        let c = dir.join("recipes/c.md");
+        fs::write(&c, recipe("recipe-c", "Recipe C")).unwrap();
+        fs::remove_file(&a).unwrap();
+        let changes = workspace.refresh().unwrap();
+        assert_eq!(changes, Changes { parsed: vec![c], removed: vec![a], vocabulary: false });
+        let ids: Vec<String> = workspace.recipes().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["recipe-b", "recipe-c"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_vocabulary_change_reparses_everything() {
+        let dir = temp_dir("vocabulary");
+        fs::write(dir.join("recipes/a.md"), recipe("recipe-a", "Recipe A")).unwrap();
+        fs::write(dir.join("recipes/b.md"), recipe("recipe-b", "Recipe B")).unwrap();
+        let vocabulary = dir.join("docs/ingredients.json");
+        let mut workspace = Workspace::open(&dir.join("recipes"), &dir.join("docs/recipes.json"), &vocabulary, true, Layout::Single, false).unwrap();
+        assert!(!workspace.has_errors());
+
+        // "onion" is missing from the new vocabulary, so linting now fails everywhere
+        fs::write(&vocabulary, r#"{"sections": ["Pantry"], "ingredients": {"rice": {"section": "Pantry"}}, "units": {}}"#).unwrap();
+        let changes = workspace.refresh().unwrap();
+        assert!(changes.vocabulary);
+        assert_eq!(changes.parsed.len(), 2);
+        assert!(workspace.has_errors());
+        assert!(workspace.recipes().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_skips_duplicate_ids() {
+        let dir = temp_dir("duplicates");
+        fs::write(dir.join("recipes/a.md"), recipe("same-id", "First")).unwrap();
+        fs::write(dir.join("recipes/b.md"), recipe("same-id", "Second")).unwrap();
+        let output = dir.join("docs/recipes.json");
+        let workspace = Workspace::open(&dir.join("recipes"), &output, &dir.join("docs/ingredients.json"), false, Layout::Single, false).unwrap();
+        assert_eq!(workspace.write().unwrap(), 1);
+        let json = fs::read_to_string(&output).unwrap();
+        assert!(json.contains("\"First\""));
+        assert!(dir.join("docs/recipes-manifest.json").exists());
+        assert!(dir.join("docs/sitemap.xml").exists());
+        assert!(dir.join("docs/feed.xml").exists());
+        assert!(dir.join("docs/r/same-id.html").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_adds_imperial() {
+        let dir = temp_dir("imperial");
+        fs::write(dir.join("recipes/a.md"), TestRecipe::new("recipe-a").ingredients("- 250 ml [onion]").source()).unwrap();
+        let output = dir.join("docs/recipes.json");
+        let workspace = Workspace::open(&dir.join("recipes"), &output, &dir.join("docs/ingredients.json"), false, Layout::Single, true).unwrap();
+        workspace.write().unwrap();
+        assert!(fs::read_to_string(&output).unwrap().contains("\"text\": \"1 cup\""));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_makes_no_deltas() {
+        let dir = temp_dir("deltas");
+        let a = dir.join("recipes/a.md");
+        fs::write(&a, recipe("recipe-a", "Recipe A")).unwrap();
+        let output = dir.join("docs/recipes.json");
+        let mut workspace = Workspace::open(&dir.join("recipes"), &output, &dir.join("docs/ingredients.json"), false, Layout::Single, false).unwrap();
+        workspace.write().unwrap();
+        fs::write(&a, recipe("recipe-a", "Recipe A, renamed")).unwrap();
+        workspace.refresh().unwrap();
+        workspace.write().unwrap();
+
+        assert!(!dir.join("docs/deltas").exists());
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("docs/recipes-manifest.json")).unwrap()).unwrap();
+        assert!(manifest.get("deltas").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
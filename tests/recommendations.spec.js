@@ -69,6 +69,29 @@ test.describe('Recipe Recommendations', () => {
     expect(unknown).toEqual([]);
   });
 
+  test('uses precomputed similar-recipes.json, filtered by settings', async ({ page }) => {
+    await page.route('**/similar-recipes.json', route => {
+      route.fulfill({
+        status: 200,
+        contentType: 'application/json',
+        body: JSON.stringify({
+          'test-curry': [
+            { id: 'test-soup', score: 9, shared: ['onion'] },
+            { id: 'test-salad', score: 1.5, shared: ['garlic'] },
+          ],
+        }),
+      });
+    });
+    await page.reload();
+
+    const results = await page.evaluate(async () => getSimilarRecipes('test-curry'));
+
+    // test-soup is untested, so hidden by default
+    expect(results.map(r => r.recipe.id)).toEqual(['test-salad']);
+    expect(results[0].score).toBe(1.5);
+    expect(results[0].sharedIngredients).toEqual(['garlic']);
+  });
+
   test('IDF scoring: rare ingredients score higher; Fresh weighted above Pantry', async ({ page }) => {
     const result = await page.evaluate(async () => {
       const recipes = await getRecipes();